@@ -0,0 +1,74 @@
+#!/usr/bin/env python
+#
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+"""
+A stand-in server for the HTTP backend of `rustc -Z codegen-cache`, which
+keeps the cache entries in a local directory.
+
+    python codegen-cache-server.py <dir> [<port>]
+
+serves `<dir>` on `localhost:<port>` (8000 by default), to be used with
+`-Z codegen-cache=http://localhost:<port>/`. See
+`src/librustc_trans/back/artifact_cache.rs` for the protocol.
+"""
+
+import os
+import sys
+
+try:
+    from BaseHTTPServer import BaseHTTPRequestHandler, HTTPServer
+except ImportError:
+    from http.server import BaseHTTPRequestHandler, HTTPServer
+
+
+class Handler(BaseHTTPRequestHandler):
+    def path_on_disk(self):
+        parts = [p for p in self.path.split('/') if p]
+        if not parts or any(p in ('.', '..') for p in parts):
+            return None
+        return os.path.join(root, *parts)
+
+    def do_GET(self):
+        path = self.path_on_disk()
+        if path is None or not os.path.isfile(path):
+            self.send_error(404)
+            return
+        with open(path, 'rb') as f:
+            data = f.read()
+        self.send_response(200)
+        self.send_header('Content-Length', str(len(data)))
+        self.end_headers()
+        self.wfile.write(data)
+
+    def do_PUT(self):
+        path = self.path_on_disk()
+        if path is None:
+            self.send_error(400)
+            return
+        data = self.rfile.read(int(self.headers['Content-Length']))
+        if not os.path.isdir(os.path.dirname(path)):
+            os.makedirs(os.path.dirname(path))
+        tmp_path = '%s.%d.tmp' % (path, os.getpid())
+        with open(tmp_path, 'wb') as f:
+            f.write(data)
+        os.rename(tmp_path, path)
+        self.send_response(201)
+        self.end_headers()
+
+
+if __name__ == '__main__':
+    if len(sys.argv) < 2:
+        sys.stderr.write(__doc__)
+        sys.exit(1)
+    root = sys.argv[1]
+    port = int(sys.argv[2]) if len(sys.argv) > 2 else 8000
+    HTTPServer(('localhost', port), Handler).serve_forever()
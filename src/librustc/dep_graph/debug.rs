@@ -32,6 +32,11 @@ impl DepNodeFilter {
         }
     }
 
+    /// The text of the filter.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
     /// True if all nodes always pass the filter.
     pub fn accepts_all(&self) -> bool {
         self.text.is_empty()
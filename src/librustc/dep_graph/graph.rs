@@ -508,6 +508,12 @@ impl DepGraph {
         }
     }
 
+    /// The dep-graph of the previous compilation session, if we are
+    /// building the dep-graph at all.
+    pub fn previous_graph(&self) -> Option<&PreviousDepGraph> {
+        self.data.as_ref().map(|data| &data.previous)
+    }
+
     pub fn node_color(&self, dep_node: &DepNode) -> Option<DepNodeColor> {
         if let Some(ref data) = self.data {
             if let Some(prev_index) = data.previous.node_to_index_opt(dep_node) {
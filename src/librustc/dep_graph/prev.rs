@@ -39,6 +39,13 @@ impl PreviousDepGraph {
             })
     }
 
+    #[inline]
+    pub fn edge_targets_from(&self,
+                             dep_node_index: SerializedDepNodeIndex)
+                             -> &[SerializedDepNodeIndex] {
+        self.data.edge_targets_from(dep_node_index)
+    }
+
     #[inline]
     pub fn index_to_node(&self, dep_node_index: SerializedDepNodeIndex) -> DepNode {
         self.data.nodes[dep_node_index].0
@@ -208,6 +208,8 @@ pub struct Stats {
     pub llvm_insns: FxHashMap<String, usize>,
     // (ident, llvm-instructions)
     pub fn_stats: Vec<(String, usize)>,
+    // (symbol name, llvm-instructions), collected for `-Z print-mono-bloat`
+    pub instance_insns: Vec<(String, usize)>,
 }
 
 impl_stable_hash_for!(struct self::Stats {
@@ -219,7 +221,8 @@ impl_stable_hash_for!(struct self::Stats {
     n_closures,
     n_llvm_insns,
     llvm_insns,
-    fn_stats
+    fn_stats,
+    instance_insns
 });
 
 impl Stats {
@@ -236,6 +239,7 @@ impl Stats {
             *self.llvm_insns.entry(k).or_insert(0) += v;
         }
         self.fn_stats.extend(stats.fn_stats);
+        self.instance_insns.extend(stats.instance_insns);
     }
 }
 
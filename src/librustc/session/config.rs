@@ -93,6 +93,26 @@ pub enum Lto {
     Fat,
 }
 
+/// What `-Z apply-suggestions` does with the suggestions.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ApplySuggestions {
+    /// Write the fixed sources back to the source files
+    Write,
+    /// Print the changes as a unified diff instead
+    Diff,
+}
+
+/// A format `-Z profile-queries` writes its profile in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ProfileQueriesFormat {
+    /// An HTML page of nested boxes, plus a text file of counts per query
+    Html,
+    /// Chrome's trace event format, viewable in chrome://tracing or Perfetto
+    ChromeTrace,
+    /// Folded stacks, as expected by flamegraph tools
+    Folded,
+}
+
 #[derive(Clone, Copy, PartialEq, Hash)]
 pub enum DebugInfoLevel {
     NoDebugInfo,
@@ -205,6 +225,7 @@ pub enum ErrorOutputType {
     HumanReadable(ColorConfig),
     Json(bool),
     Short(ColorConfig),
+    Sarif,
 }
 
 impl Default for ErrorOutputType {
@@ -593,6 +614,7 @@ impl Options {
     pub fn build_dep_graph(&self) -> bool {
         self.incremental.is_some() || self.debugging_opts.dump_dep_graph
             || self.debugging_opts.query_dep_graph
+            || self.debugging_opts.explain_dep.is_some()
     }
 
     #[inline(always)]
@@ -757,6 +779,8 @@ macro_rules! options {
             Some("a space-separated list of passes, or `all`");
         pub const parse_opt_uint: Option<&'static str> =
             Some("a number");
+        pub const parse_opt_size: Option<&'static str> =
+            Some("a size in bytes, optionally followed by `K`, `M` or `G`");
         pub const parse_panic_strategy: Option<&'static str> =
             Some("either `panic` or `abort`");
         pub const parse_relro_level: Option<&'static str> =
@@ -773,11 +797,16 @@ macro_rules! options {
             Some("one of `thin`, `fat`, or omitted");
         pub const parse_epoch: Option<&'static str> =
             Some("one of: `2015`, `2018`");
+        pub const parse_apply_suggestions: Option<&'static str> =
+            Some("`diff` or omitted");
+        pub const parse_profile_queries_format: Option<&'static str> =
+            Some("a comma-separated list of `html`, `chrome` or `folded`");
     }
 
     #[allow(dead_code)]
     mod $mod_set {
-        use super::{$struct_name, Passes, SomePasses, AllPasses, Sanitizer, Lto};
+        use super::{$struct_name, Passes, SomePasses, AllPasses, Sanitizer, Lto,
+                    ApplySuggestions, ProfileQueriesFormat};
         use rustc_back::{LinkerFlavor, PanicStrategy, RelroLevel};
         use std::path::PathBuf;
         use syntax::epoch::Epoch;
@@ -888,6 +917,21 @@ macro_rules! options {
             }
         }
 
+        fn parse_opt_size(slot: &mut Option<u64>, v: Option<&str>) -> bool {
+            let s = match v {
+                Some(s) => s,
+                None => return false,
+            };
+            let (digits, multiplier) = match s.chars().last() {
+                Some('K') | Some('k') => (&s[..s.len() - 1], 1 << 10),
+                Some('M') | Some('m') => (&s[..s.len() - 1], 1 << 20),
+                Some('G') | Some('g') => (&s[..s.len() - 1], 1 << 30),
+                _ => (s, 1),
+            };
+            *slot = digits.parse::<u64>().ok().and_then(|n| n.checked_mul(multiplier));
+            slot.is_some()
+        }
+
         fn parse_passes(slot: &mut Passes, v: Option<&str>) -> bool {
             match v {
                 Some("all") => {
@@ -983,6 +1027,35 @@ macro_rules! options {
             true
         }
 
+        fn parse_apply_suggestions(slot: &mut Option<ApplySuggestions>,
+                                   v: Option<&str>) -> bool {
+            *slot = match v {
+                None => Some(ApplySuggestions::Write),
+                Some("diff") => Some(ApplySuggestions::Diff),
+                Some(_) => return false,
+            };
+            true
+        }
+
+        fn parse_profile_queries_format(slot: &mut Vec<ProfileQueriesFormat>,
+                                        v: Option<&str>) -> bool {
+            let v = match v {
+                Some(v) => v,
+                None => return false,
+            };
+            let mut formats = vec![];
+            for format in v.split(',') {
+                formats.push(match format {
+                    "html" => ProfileQueriesFormat::Html,
+                    "chrome" => ProfileQueriesFormat::ChromeTrace,
+                    "folded" => ProfileQueriesFormat::Folded,
+                    _ => return false,
+                });
+            }
+            *slot = formats;
+            true
+        }
+
         fn parse_epoch(slot: &mut Epoch, v: Option<&str>) -> bool {
             match v {
                 Some(s) => {
@@ -1072,6 +1145,9 @@ options! {CodegenOptions, CodegenSetter, basic_codegen_options,
         [TRACKED], "panic strategy to compile crate with"),
     incremental: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "enable incremental compilation"),
+    incremental_max_size: Option<u64> = (None, parse_opt_size, [UNTRACKED],
+          "evict the least recently used sessions of all crates in the incremental \
+           compilation directory once it grows larger than this"),
 }
 
 options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
@@ -1166,20 +1242,48 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "enable incremental compilation support for queries (experimental)"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof)"),
+    incremental_changes: bool = (false, parse_bool, [UNTRACKED],
+        "print the inputs that changed since the previous incremental compilation session \
+         and the queries that were re-executed because of them"),
     incremental_dump_hash: bool = (false, parse_bool, [UNTRACKED],
         "dump hash information in textual format to stdout"),
     incremental_verify_ich: bool = (false, parse_bool, [UNTRACKED],
         "verify incr. comp. hashes of green query instances"),
     incremental_ignore_spans: bool = (false, parse_bool, [UNTRACKED],
         "ignore spans during ICH computation -- used for testing"),
+    codegen_cache: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "fetch the object files of codegen units from, and store them in, the given \
+         directory or `http://` URL instead of running LLVM on them again"),
     dump_dep_graph: bool = (false, parse_bool, [UNTRACKED],
           "dump the dependency graph to $RUST_DEP_GRAPH (default: /tmp/dep_graph.gv)"),
     query_dep_graph: bool = (false, parse_bool, [UNTRACKED],
           "enable queries of the dependency graph for regression testing"),
+    explain_dep: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "print the shortest paths along which changes to the dep-nodes matching SOURCE \
+          would reach the ones matching TARGET (`SOURCE -> TARGET`, where both are like \
+          `RUST_DEP_GRAPH_FILTER` filters)"),
+    dump_query_cycle: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "write the queries of a cycle error to the given file in graphviz format"),
     profile_queries: bool = (false, parse_bool, [UNTRACKED],
           "trace and profile the queries of the incremental compilation framework"),
     profile_queries_and_keys: bool = (false, parse_bool, [UNTRACKED],
           "trace and profile the queries and keys of the incremental compilation framework"),
+    profile_queries_format: Vec<ProfileQueriesFormat> = (vec![ProfileQueriesFormat::Html],
+          parse_profile_queries_format, [UNTRACKED],
+          "formats to write the profile of -Z profile-queries in: `html` (the default),
+          `chrome` (Chrome trace events) and/or `folded` (folded stacks for flamegraphs)"),
+    self_profile: bool = (false, parse_bool, [UNTRACKED],
+          "print the items that took the most time to compile and write the time spent on
+          every item to self_profile.json"),
+    self_profile_top: usize = (20, parse_uint, [UNTRACKED],
+          "number of items to print with -Z self-profile (default: 20)"),
+    print_mono_bloat: bool = (false, parse_bool, [UNTRACKED_WITH_WARNING(true,
+        "The LLVM instruction counts of `-Z print-mono-bloat` only cover re-translated \
+         codegen units when used with incremental compilation")],
+          "print the generic definitions whose instantiations are the largest, and what \
+           caused each instantiation"),
+    mono_bloat_top: usize = (20, parse_uint, [UNTRACKED],
+          "number of definitions to print with -Z print-mono-bloat (default: 20)"),
     no_analysis: bool = (false, parse_bool, [UNTRACKED],
           "parse and expand the source, but run no analysis"),
     extra_plugins: Vec<String> = (Vec::new(), parse_list, [TRACKED],
@@ -1204,8 +1308,17 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "print layout information for each type encountered"),
     print_trans_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "print the result of the translation item collection pass"),
+    print_cgu_partitioning: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "write the codegen units, their size estimates and the items placed in them \
+           to the given file as JSON"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
           "set the MIR optimization level (0-3, default: 1)"),
+    inline_mir: Option<bool> = (None, parse_opt_bool, [TRACKED],
+          "inline functions at the MIR level (default: when optimizing)"),
+    print_mir_inlining: bool = (false, parse_bool, [UNTRACKED_WITH_WARNING(true,
+        "`-Z print-mir-inlining` only prints the decisions for functions whose MIR is \
+         optimized again when used with incremental compilation")],
+          "print which calls are inlined at the MIR level and why"),
     mutable_noalias: bool = (false, parse_bool, [UNTRACKED],
           "emit noalias metadata for mutable references"),
     dump_mir: Option<String> = (None, parse_opt_string, [UNTRACKED],
@@ -1273,6 +1386,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
          themselves"),
     approximate_suggestions: bool = (false, parse_bool, [UNTRACKED],
         "include machine-applicability of suggestions in JSON output"),
+    apply_suggestions: Option<ApplySuggestions> = (None, parse_apply_suggestions, [UNTRACKED],
+        "apply all machine-applicable suggestions to the source files at the end of \
+         compilation, or print them as a unified diff with `-Z apply-suggestions=diff`"),
     unpretty: Option<String> = (None, parse_unpretty, [UNTRACKED],
         "Present the input source, unstable (and less-pretty) variants;
         valid types are any of the types for `--pretty`, as well as:
@@ -1620,7 +1736,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "",
             "error-format",
             "How errors and other messages are produced",
-            "human|json|short",
+            "human|json|short|sarif",
         ),
         opt::opt_s(
             "",
@@ -1640,6 +1756,15 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
                   `expanded,identified` (fully parenthesized, AST nodes with IDs).",
             "TYPE",
         ),
+        opt::opt(
+            "",
+            "explain-format",
+            "Format of the output of --explain: `markdown` (the raw explanation),
+                  `terminal` (rendered with colors, the default when printing to
+                  a terminal), or `json` (all explanations as JSON, or the one
+                  passed to --explain)",
+            "markdown|terminal|json",
+        ),
         opt::multi_s(
             "",
             "remap-path-prefix",
@@ -1721,13 +1846,26 @@ pub fn build_session_options_and_crate_config(
                     );
                 }
             }
+            Some("sarif") => {
+                if nightly_options::is_unstable_enabled(matches) {
+                    ErrorOutputType::Sarif
+                } else {
+                    early_error(
+                        ErrorOutputType::default(),
+                        &format!(
+                            "the `-Z unstable-options` flag must also be passed to \
+                             enable the SARIF error format"
+                        ),
+                    );
+                }
+            }
             None => ErrorOutputType::HumanReadable(color),
 
             Some(arg) => early_error(
                 ErrorOutputType::HumanReadable(color),
                 &format!(
-                    "argument for --error-format must be `human`, `json` or \
-                     `short` (instead was `{}`)",
+                    "argument for --error-format must be `human`, `json`, `short` \
+                     or `sarif` (instead was `{}`)",
                     arg
                 ),
             ),
@@ -2815,6 +2953,9 @@ mod tests {
         opts.cg.incremental = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
 
+        opts.cg.incremental_max_size = Some(1 << 30);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+
         // Make sure changing a [TRACKED] option changes the hash
         opts = reference.clone();
         opts.cg.lto = Lto::Fat;
@@ -2923,6 +3064,10 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.trans_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_mono_bloat = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_mir_inlining = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.borrowck_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.meta_stats = true;
@@ -2969,6 +3114,8 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.print_trans_items = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_cgu_partitioning = Some(String::from("abc"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir_dir = String::from("abc");
@@ -3025,6 +3172,10 @@ mod tests {
         opts.debugging_opts.mir_opt_level = 3;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
 
+        opts = reference.clone();
+        opts.debugging_opts.inline_mir = Some(false);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
         opts = reference.clone();
         opts.debugging_opts.relro_level = Some(RelroLevel::Full);
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
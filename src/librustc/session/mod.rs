@@ -33,6 +33,7 @@ use errors::{self, DiagnosticBuilder, DiagnosticId};
 use errors::emitter::{Emitter, EmitterWriter};
 use syntax::epoch::Epoch;
 use syntax::json::JsonEmitter;
+use syntax::sarif::{self, SarifEmitter};
 use syntax::feature_gate;
 use syntax::symbol::Symbol;
 use syntax::parse;
@@ -510,6 +511,9 @@ impl Session {
     pub fn profile_queries_and_keys(&self) -> bool {
         self.opts.debugging_opts.profile_queries_and_keys
     }
+    pub fn self_profiling(&self) -> bool {
+        self.opts.debugging_opts.self_profile
+    }
     pub fn count_llvm_insns(&self) -> bool {
         self.opts.debugging_opts.count_llvm_insns
     }
@@ -1027,6 +1031,8 @@ pub fn build_session_with_codemap(
 
     let external_macro_backtrace = sopts.debugging_opts.external_macro_backtrace;
 
+    let collect_suggestions = sopts.debugging_opts.apply_suggestions.is_some();
+
     let emitter: Box<dyn Emitter> =
         match (sopts.error_format, emitter_dest) {
             (config::ErrorOutputType::HumanReadable(color_config), None) => Box::new(
@@ -1064,6 +1070,12 @@ pub fn build_session_with_codemap(
             (config::ErrorOutputType::Short(_), Some(dst)) => {
                 Box::new(EmitterWriter::new(dst, Some(codemap.clone()), true, false))
             }
+            (config::ErrorOutputType::Sarif, None) => Box::new(
+                SarifEmitter::stderr(Some(registry), codemap.clone()),
+            ),
+            (config::ErrorOutputType::Sarif, Some(dst)) => Box::new(
+                SarifEmitter::new(dst, Some(registry), codemap.clone()),
+            ),
         };
 
     let diagnostic_handler = errors::Handler::with_emitter_and_flags(
@@ -1072,6 +1084,7 @@ pub fn build_session_with_codemap(
             can_emit_warnings,
             treat_err_as_bug,
             external_macro_backtrace,
+            collect_suggestions,
             ..Default::default()
         },
     );
@@ -1246,6 +1259,15 @@ pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
         config::ErrorOutputType::Short(color_config) => {
             Box::new(EmitterWriter::stderr(color_config, None, true, false))
         }
+        config::ErrorOutputType::Sarif => {
+            // Written with the log of the session if there is one, so that
+            // the compilation still writes a single log.
+            sarif::buffer_early_diagnostic(errors::Level::Fatal, msg);
+            if !sarif::has_live_emitter() {
+                drop(SarifEmitter::basic());
+            }
+            errors::FatalError.raise();
+        }
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Fatal);
@@ -1261,6 +1283,11 @@ pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
         config::ErrorOutputType::Short(color_config) => {
             Box::new(EmitterWriter::stderr(color_config, None, true, false))
         }
+        config::ErrorOutputType::Sarif => {
+            // Written with the log of the session.
+            sarif::buffer_early_diagnostic(errors::Level::Warning, msg);
+            return;
+        }
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Warning);
@@ -0,0 +1,80 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Writes the queries of a cycle error to a file in graphviz format
+//! (`-Z dump-query-cycle`).
+
+use graphviz as dot;
+use graphviz::IntoCow;
+use ty::TyCtxt;
+
+use std::fs::File;
+use std::io;
+use syntax_pos::{Span, DUMMY_SP};
+
+/// The queries of a cycle, in the order they required each other. The
+/// labels consist of the description of the query and where it was
+/// required.
+struct CycleGraph {
+    labels: Vec<String>,
+}
+
+/// Every query requires the next one, and the last one requires the first.
+type Edge = (usize, usize);
+
+pub(super) fn dump_cycle(tcx: TyCtxt, path: &str, frames: &[(Span, String)]) -> io::Result<()> {
+    let labels = frames.iter().map(|&(span, ref description)| {
+        // Backslashes start escape sequences in these labels.
+        let description = description.replace('\\', "\\\\");
+        if span == DUMMY_SP {
+            description
+        } else {
+            let location = tcx.sess.codemap().span_to_string(span).replace('\\', "\\\\");
+            format!("{}\\n{}", description, location)
+        }
+    }).collect();
+    let mut file = File::create(path)?;
+    dot::render(&CycleGraph { labels }, &mut file)
+}
+
+impl<'a> dot::GraphWalk<'a> for CycleGraph {
+    type Node = usize;
+    type Edge = Edge;
+    fn nodes(&self) -> dot::Nodes<usize> {
+        (0..self.labels.len()).collect::<Vec<_>>().into_cow()
+    }
+    fn edges(&self) -> dot::Edges<Edge> {
+        let n = self.labels.len();
+        (0..n).map(|i| (i, (i + 1) % n)).collect::<Vec<_>>().into_cow()
+    }
+    fn source(&self, edge: &Edge) -> usize {
+        edge.0
+    }
+    fn target(&self, edge: &Edge) -> usize {
+        edge.1
+    }
+}
+
+impl<'a> dot::Labeller<'a> for CycleGraph {
+    type Node = usize;
+    type Edge = Edge;
+    fn graph_id(&self) -> dot::Id {
+        dot::Id::new("QueryCycle").unwrap()
+    }
+    fn node_id(&self, n: &usize) -> dot::Id {
+        dot::Id::new(format!("query{}", n)).unwrap()
+    }
+    fn node_label(&self, n: &usize) -> dot::LabelText {
+        dot::LabelText::EscStr(self.labels[*n].clone().into_cow())
+    }
+    fn edge_label(&self, _: &Edge) -> dot::LabelText {
+        dot::LabelText::label("requires")
+    }
+}
@@ -32,6 +32,23 @@ pub trait Key: Clone + Hash + Eq + Debug {
     /// In the event that a cycle occurs, if no explicit span has been
     /// given for a query with key `self`, what span should we use?
     fn default_span(&self, tcx: TyCtxt) -> Span;
+
+    /// The span to point to for a query with key `self` in the notes of a
+    /// cycle error. Unlike `default_span`, this is only computed once a
+    /// cycle has been found, so it may run queries of its own.
+    fn cycle_span(&self, tcx: TyCtxt) -> Span {
+        let span = self.default_span(tcx);
+        match self.query_def_id() {
+            Some(def_id) if span == DUMMY_SP => tcx.def_span(def_id),
+            _ => span,
+        }
+    }
+
+    /// The definition this key refers to, if any. `-Z self-profile`
+    /// attributes the time spent on the query to it.
+    fn query_def_id(&self) -> Option<DefId> {
+        None
+    }
 }
 
 impl<'tcx> Key for ty::InstanceDef<'tcx> {
@@ -42,6 +59,10 @@ impl<'tcx> Key for ty::InstanceDef<'tcx> {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         tcx.def_span(self.def_id())
     }
+
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.def_id())
+    }
 }
 
 impl<'tcx> Key for ty::Instance<'tcx> {
@@ -52,6 +73,10 @@ impl<'tcx> Key for ty::Instance<'tcx> {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         tcx.def_span(self.def_id())
     }
+
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.def_id())
+    }
 }
 
 impl<'tcx> Key for mir::interpret::GlobalId<'tcx> {
@@ -62,6 +87,10 @@ impl<'tcx> Key for mir::interpret::GlobalId<'tcx> {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.instance.default_span(tcx)
     }
+
+    fn query_def_id(&self) -> Option<DefId> {
+        self.instance.query_def_id()
+    }
 }
 
 impl Key for CrateNum {
@@ -80,6 +109,9 @@ impl Key for DefIndex {
     fn default_span(&self, _tcx: TyCtxt) -> Span {
         DUMMY_SP
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(DefId::local(*self))
+    }
 }
 
 impl Key for DefId {
@@ -89,6 +121,9 @@ impl Key for DefId {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         tcx.def_span(*self)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(*self)
+    }
 }
 
 impl Key for (DefId, DefId) {
@@ -98,6 +133,9 @@ impl Key for (DefId, DefId) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.1.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.0)
+    }
 }
 
 impl Key for (CrateNum, DefId) {
@@ -107,6 +145,9 @@ impl Key for (CrateNum, DefId) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.1.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.1)
+    }
 }
 
 impl Key for (DefId, SimplifiedType) {
@@ -116,6 +157,9 @@ impl Key for (DefId, SimplifiedType) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.0.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.0)
+    }
 }
 
 impl<'tcx> Key for (DefId, &'tcx Substs<'tcx>) {
@@ -125,6 +169,9 @@ impl<'tcx> Key for (DefId, &'tcx Substs<'tcx>) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.0.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.0)
+    }
 }
 
 impl<'tcx> Key for (ty::ParamEnv<'tcx>, ty::PolyTraitRef<'tcx>) {
@@ -134,6 +181,9 @@ impl<'tcx> Key for (ty::ParamEnv<'tcx>, ty::PolyTraitRef<'tcx>) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         tcx.def_span(self.1.def_id())
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.1.def_id())
+    }
 }
 
 impl<'tcx> Key for ty::PolyTraitRef<'tcx>{
@@ -143,6 +193,9 @@ impl<'tcx> Key for ty::PolyTraitRef<'tcx>{
     fn default_span(&self, tcx: TyCtxt) -> Span {
         tcx.def_span(self.def_id())
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.def_id())
+    }
 }
 
 impl<'tcx> Key for Ty<'tcx> {
@@ -152,6 +205,17 @@ impl<'tcx> Key for Ty<'tcx> {
     fn default_span(&self, _: TyCtxt) -> Span {
         DUMMY_SP
     }
+    fn cycle_span(&self, tcx: TyCtxt) -> Span {
+        match self.sty {
+            ty::TyAdt(def, _) => tcx.def_span(def.did),
+            ty::TyForeign(def_id) |
+            ty::TyFnDef(def_id, _) |
+            ty::TyClosure(def_id, _) |
+            ty::TyGenerator(def_id, ..) |
+            ty::TyAnon(def_id, _) => tcx.def_span(def_id),
+            _ => DUMMY_SP,
+        }
+    }
 }
 
 impl<'tcx, T: Key> Key for ty::ParamEnvAnd<'tcx, T> {
@@ -161,6 +225,12 @@ impl<'tcx, T: Key> Key for ty::ParamEnvAnd<'tcx, T> {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.value.default_span(tcx)
     }
+    fn cycle_span(&self, tcx: TyCtxt) -> Span {
+        self.value.cycle_span(tcx)
+    }
+    fn query_def_id(&self) -> Option<DefId> {
+        self.value.query_def_id()
+    }
 }
 
 impl Key for InternedString {
@@ -76,6 +76,8 @@ mod config;
 pub use self::config::QueryConfig;
 use self::config::QueryDescription;
 
+mod cycle;
+
 mod on_disk_cache;
 pub use self::on_disk_cache::OnDiskCache;
 
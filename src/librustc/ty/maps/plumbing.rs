@@ -16,6 +16,7 @@ use dep_graph::{DepNodeIndex, DepNode, DepKind, DepNodeColor};
 use errors::DiagnosticBuilder;
 use ty::{TyCtxt};
 use ty::maps::Query; // NB: actually generated by the macros in this file
+use ty::maps::cycle;
 use ty::maps::config::QueryDescription;
 use ty::item_path;
 
@@ -23,7 +24,7 @@ use rustc_data_structures::fx::{FxHashMap};
 use std::cell::{Ref, RefMut};
 use std::marker::PhantomData;
 use std::mem;
-use syntax_pos::Span;
+use syntax_pos::{Span, DUMMY_SP};
 
 pub(super) struct QueryMap<'tcx, D: QueryDescription<'tcx>> {
     phantom: PhantomData<(D, &'tcx ())>,
@@ -81,22 +82,47 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
         // (And cycle errors around impls tend to occur during the
         // collect/coherence phases anyhow.)
         item_path::with_forced_impl_filename_line(|| {
-            let span = self.sess.codemap().def_span(span);
+            let span = self.sess.codemap().def_span(stack[0].1.cycle_span(self, span));
             let mut err =
                 struct_span_err!(self.sess, span, E0391,
                                  "cyclic dependency detected");
             err.span_label(span, "cyclic reference");
 
-            err.span_note(self.sess.codemap().def_span(stack[0].0),
-                          &format!("the cycle begins when {}...", stack[0].1.describe(self)));
+            // Every query in the cycle, along with the span of where it was
+            // required or, failing that, the span of its key. Queries that
+            // describe themselves the same way and point to the same span
+            // as the one before are left out, since they'd just repeat it.
+            let mut frames: Vec<(Span, String)> = vec![];
+            for &(span, ref query) in &stack {
+                let span = self.sess.codemap().def_span(query.cycle_span(self, span));
+                let description = query.describe(self);
+                if frames.last() != Some(&(span, description.clone())) {
+                    frames.push((span, description));
+                }
+            }
 
-            for &(span, ref query) in &stack[1..] {
-                err.span_note(self.sess.codemap().def_span(span),
-                              &format!("...which then requires {}...", query.describe(self)));
+            for (i, &(span, ref description)) in frames.iter().enumerate() {
+                let msg = if i == 0 {
+                    format!("the cycle begins when {}...", description)
+                } else {
+                    format!("...which then requires {}...", description)
+                };
+                if span == DUMMY_SP {
+                    err.note(&msg);
+                } else {
+                    err.span_note(span, &msg);
+                }
             }
 
             err.note(&format!("...which then again requires {}, completing the cycle.",
-                              stack[0].1.describe(self)));
+                              frames[0].1));
+
+            if let Some(ref path) = self.sess.opts.debugging_opts.dump_query_cycle {
+                if let Err(e) = cycle::dump_cycle(self, path, &frames) {
+                    self.sess.warn(&format!("could not write the query cycle to `{}`: {}",
+                                            path, e));
+                }
+            }
 
             return err
         })
@@ -160,13 +186,14 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
     }
 }
 
-// If enabled, send a message to the profile-queries thread
+// If enabled, send a message to the profile-queries thread. Unlike
+// `-Z profile-queries`, `-Z self-profile` also works in compilers built
+// without debug assertions.
 macro_rules! profq_msg {
     ($tcx:expr, $msg:expr) => {
-        if cfg!(debug_assertions) {
-            if $tcx.sess.profile_queries() {
-                profq_msg($tcx.sess, $msg)
-            }
+        if (cfg!(debug_assertions) && $tcx.sess.profile_queries()) ||
+           $tcx.sess.self_profiling() {
+            profq_msg($tcx.sess, $msg)
         }
     }
 }
@@ -232,6 +259,23 @@ macro_rules! define_maps {
             $($name(Option<String>)),*
         }
 
+        impl QueryMsg {
+            /// The name of the query.
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    $(QueryMsg::$name(_) => stringify!($name)),*
+                }
+            }
+
+            /// The key of the query, if keys are being recorded
+            /// (`-Z profile-queries-and-keys`).
+            pub fn key(&self) -> Option<&str> {
+                match *self {
+                    $(QueryMsg::$name(ref key) => key.as_ref().map(|k| &k[..])),*
+                }
+            }
+        }
+
         impl<$tcx> Query<$tcx> {
             pub fn describe(&self, tcx: TyCtxt) -> String {
                 let (r, name) = match *self {
@@ -245,6 +289,22 @@ macro_rules! define_maps {
                     r
                 }
             }
+
+            /// Returns `span`, the span the query was required at, or the
+            /// span of the key of the query if `span` is a dummy span.
+            pub fn cycle_span(&self, tcx: TyCtxt, span: Span) -> Span {
+                if span != DUMMY_SP {
+                    return span;
+                }
+                // The span of most keys is computed by the `def_span` query,
+                // which must not depend on itself.
+                if let Query::def_span(..) = *self {
+                    return span;
+                }
+                match *self {
+                    $(Query::$name(key) => key.cycle_span(tcx)),*
+                }
+            }
         }
 
         pub mod queries {
@@ -290,7 +350,8 @@ macro_rules! define_maps {
                 profq_msg!(tcx,
                     ProfileQueriesMsg::QueryBegin(
                         span.data(),
-                        QueryMsg::$name(profq_key!(tcx, key))
+                        QueryMsg::$name(profq_key!(tcx, key)),
+                        key.query_def_id()
                     )
                 );
 
@@ -725,7 +786,8 @@ pub fn force_from_dep_node<'a, 'gcx, 'lcx>(tcx: TyCtxt<'a, 'gcx, 'lcx>,
                 profq_msg!(tcx,
                     ProfileQueriesMsg::QueryBegin(
                         span.data(),
-                        ::ty::maps::QueryMsg::$query(profq_key!(tcx, $key))
+                        ::ty::maps::QueryMsg::$query(profq_key!(tcx, $key)),
+                        $key.query_def_id()
                     )
                 );
 
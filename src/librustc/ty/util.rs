@@ -558,6 +558,27 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
         def_id
     }
 
+    /// Whether the optimized MIR of the local fn, method or closure `def_id`
+    /// is encoded in the crate metadata, so that other crates can inline or
+    /// instantiate it. MIR that is exported this way must not refer to
+    /// symbols that are not exported.
+    pub fn is_mir_exported(self, def_id: DefId) -> bool {
+        if self.is_closure(def_id) || self.is_const_fn(def_id) ||
+           self.sess.opts.debugging_opts.always_encode_mir {
+            return true;
+        }
+        // Provided methods of traits are instantiated by the implementing
+        // crates.
+        if let Some(item) = self.opt_associated_item(def_id) {
+            if let ty::TraitContainer(_) = item.container {
+                return true;
+            }
+        }
+        let generics = self.generics_of(def_id);
+        generics.parent_types as usize + generics.types.len() > 0 ||
+            self.trans_fn_attrs(def_id).requests_inline()
+    }
+
     /// Given the def-id and substs a closure, creates the type of
     /// `self` argument that the closure expects. For example, for a
     /// `Fn` closure, this would return a reference type `&T` where
@@ -24,9 +24,11 @@ use std::sync::mpsc::{Sender};
 use syntax_pos::{SpanData};
 use ty::maps::{QueryMsg};
 use dep_graph::{DepNode};
+use hir::def_id::DefId;
 use proc_macro;
 use lazy_static;
 use session::Session;
+use session::config::ProfileQueriesFormat;
 
 // The name of the associated type for `Fn` return types
 pub const FN_OUTPUT_NAME: &'static str = "Output";
@@ -65,8 +67,26 @@ pub struct ProfQDumpParams {
     pub ack:Sender<()>,
     /// toggle dumping a log file with every `ProfileQueriesMsg`
     pub dump_profq_msg_log:bool,
+    /// The formats to write the profile in
+    pub formats:Vec<ProfileQueriesFormat>,
 }
 
+/// What `-Z self-profile` attributes compile time to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProfQItem {
+    /// Queries whose key refers to this definition
+    Def(DefId),
+    /// Translating a `TransItem` of this definition to LLVM IR; the string
+    /// describes the item, including its substitutions
+    TransItem(DefId, String),
+    /// Everything else
+    Other,
+}
+
+/// The self time spent on every item, per query (or pass, or `"trans_item"`
+/// for the translation of `TransItem`s).
+pub type ProfQItemTimes = HashMap<(ProfQItem, String), Duration>;
+
 /// A sequence of these messages induce a trace of query-based incremental compilation.
 /// FIXME(matthewhammer): Determine whether we should include cycle detection here or not.
 #[derive(Clone,Debug)]
@@ -79,17 +99,24 @@ pub enum ProfileQueriesMsg {
     TaskBegin(DepNode),
     /// end a task
     TaskEnd,
-    /// begin a new query
+    /// begin a new query, with the definition its key refers to, if any
     /// can't use `Span` because queries are sent to other thread
-    QueryBegin(SpanData, QueryMsg),
+    QueryBegin(SpanData, QueryMsg, Option<DefId>),
     /// query is satisfied by using an already-known value for the given key
     CacheHit,
     /// query requires running a provider; providers may nest, permitting queries to nest.
     ProviderBegin,
     /// query is satisfied by a provider terminating with a value
     ProviderEnd,
+    /// begin translating a `TransItem` of the given definition to LLVM IR;
+    /// the string describes the item, including its substitutions
+    TransItemBegin(DefId, String),
+    /// end translating a `TransItem`
+    TransItemEnd,
     /// dump a record of the queries to the given path
     Dump(ProfQDumpParams),
+    /// send the self time spent on every item to the given channel
+    ItemTimes(Sender<ProfQItemTimes>),
     /// halt the profiling/monitoring background thread
     Halt
 }
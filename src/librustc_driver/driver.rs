@@ -89,7 +89,7 @@ pub fn compile_input(trans: Box<TransCrate>,
         }}
     }
 
-    if sess.profile_queries() {
+    if sess.profile_queries() || sess.self_profiling() {
         profile::begin(sess);
     }
 
@@ -517,7 +517,7 @@ pub fn phase_1_parse_input<'a>(control: &CompileController,
                                -> PResult<'a, ast::Crate> {
     sess.diagnostic().set_continue_after_error(control.continue_parse_after_error);
 
-    if sess.profile_queries() {
+    if sess.profile_queries() || sess.self_profiling() {
         profile::begin(sess);
     }
 
@@ -1116,6 +1116,9 @@ pub fn phase_4_translate_to_llvm<'a, 'tcx>(trans: &TransCrate,
     if tcx.sess.profile_queries() {
         profile::dump(&tcx.sess, "profile_queries".to_string())
     }
+    if tcx.sess.self_profiling() {
+        profile::self_profile::report(tcx)
+    }
 
     translation
 }
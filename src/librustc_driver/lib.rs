@@ -65,7 +65,7 @@ use rustc_save_analysis::DumpHandler;
 use rustc_data_structures::sync::Lrc;
 use rustc::session::{self, config, Session, build_session, CompileResult};
 use rustc::session::CompileIncomplete;
-use rustc::session::config::{Input, PrintRequest, ErrorOutputType};
+use rustc::session::config::{Input, PrintRequest, ErrorOutputType, ApplySuggestions};
 use rustc::session::config::nightly_options;
 use rustc::session::filesearch;
 use rustc::session::{early_error, early_warn};
@@ -78,15 +78,17 @@ use rustc_metadata::dynamic_lib::DynamicLibrary;
 use rustc::util::common::{time, ErrorReported};
 use rustc_trans_utils::trans_crate::TransCrate;
 
-use serialize::json::ToJson;
+use serialize::json::{Json, ToJson};
 
 use std::any::Any;
 use std::cmp::Ordering::Equal;
 use std::cmp::max;
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
 use std::env;
 use std::ffi::OsString;
+use std::fs::{self, File};
 use std::io::{self, Read, Write};
 use std::iter::repeat;
 use std::mem;
@@ -529,16 +531,67 @@ fn run_compiler_impl<'a>(args: &[String],
 
     let control = callbacks.build_controller(&sess, &matches);
 
-    (driver::compile_input(trans,
-                           &sess,
-                           &cstore,
-                           &input_file_path,
-                           &input,
-                           &odir,
-                           &ofile,
-                           Some(plugins),
-                           &control),
-     Some(sess))
+    let result = driver::compile_input(trans,
+                                       &sess,
+                                       &cstore,
+                                       &input_file_path,
+                                       &input,
+                                       &odir,
+                                       &ofile,
+                                       Some(plugins),
+                                       &control);
+
+    if let Some(mode) = sess.opts.debugging_opts.apply_suggestions {
+        apply_suggestions(&sess, mode);
+    }
+
+    (result, Some(sess))
+}
+
+/// Applies the suggestions collected during the compilation to the source
+/// files, or prints them as a diff (`-Z apply-suggestions`).
+fn apply_suggestions(sess: &Session, mode: ApplySuggestions) {
+    let suggestions = sess.diagnostic().take_suggestions();
+    for fixed in errors::apply::apply_suggestions(&suggestions, sess.codemap()) {
+        match mode {
+            ApplySuggestions::Diff => print!("{}", errors::apply::unified_diff(&fixed)),
+            ApplySuggestions::Write => {
+                // Only the sources of this crate are rewritten, and at their
+                // real location rather than the one `--remap-path-prefix`
+                // maps them to.
+                if fixed.file.is_imported() {
+                    continue;
+                }
+                let path = match fixed.file.unmapped_path {
+                    Some(FileName::Real(ref path)) => path,
+                    _ => continue,
+                };
+                if let Err(e) = replace_file(path, fixed.fixed.as_bytes()) {
+                    sess.err(&format!("failed to apply suggestions to {}: {}",
+                                      path.display(), e));
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the contents of the file at `path` by writing them to a
+/// temporary file next to it first, so that the file is left untouched if
+/// writing fails.
+fn replace_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().map_or(OsString::new(), |name| name.to_owned());
+    tmp_name.push(".rustc-suggestions.tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = File::create(&tmp_path)
+        .and_then(|mut file| file.write_all(contents).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::metadata(path))
+        .and_then(|metadata| fs::set_permissions(&tmp_path, metadata.permissions()))
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
 }
 
 // Extract output directory and file from matches.
@@ -700,15 +753,62 @@ fn stdout_isatty() -> bool {
     }
 }
 
-fn handle_explain(code: &str,
-                  descriptions: &errors::registry::Registry,
-                  output: ErrorOutputType) {
-    let normalised = if code.starts_with("E") {
+/// How `--explain` prints explanations (`--explain-format`).
+#[derive(Copy, Clone, PartialEq)]
+enum ExplainFormat {
+    /// The markdown of the explanation, as written
+    Markdown,
+    /// The explanation rendered with colors
+    Terminal,
+    /// A JSON array of codes and explanations
+    Json,
+}
+
+fn parse_explain_format(matches: &getopts::Matches,
+                        output: ErrorOutputType) -> Option<ExplainFormat> {
+    match matches.opt_str("explain-format").as_ref().map(|s| &s[..]) {
+        Some("markdown") => Some(ExplainFormat::Markdown),
+        Some("terminal") => Some(ExplainFormat::Terminal),
+        Some("json") => Some(ExplainFormat::Json),
+        None => None,
+        Some(arg) => early_error(output, &format!("argument for --explain-format must be \
+                                                   `markdown`, `terminal` or `json` \
+                                                   (instead was `{}`)", arg)),
+    }
+}
+
+fn normalise_error_code(code: &str) -> String {
+    if code.starts_with("E") {
         code.to_string()
     } else {
         format!("E{0:0>4}", code)
-    };
+    }
+}
+
+fn handle_explain(code: &str,
+                  descriptions: &errors::registry::Registry,
+                  output: ErrorOutputType,
+                  format: Option<ExplainFormat>) {
+    let normalised = normalise_error_code(code);
+    let format = format.unwrap_or_else(|| if stdout_isatty() {
+        ExplainFormat::Terminal
+    } else {
+        ExplainFormat::Markdown
+    });
     match descriptions.find_description(&normalised) {
+        Some(ref description) if format == ExplainFormat::Json => {
+            print_explanations_json(&[(&normalised[..], *description)]);
+        }
+        Some(ref description) if format == ExplainFormat::Terminal => {
+            // Windows consoles don't understand ANSI escape codes.
+            let color = !cfg!(windows);
+            let text = errors::markdown::render_to_string(&description[1..], color);
+            if stdout_isatty() {
+                show_content_with_pager(&text, color);
+            } else {
+                print!("{}", text);
+            }
+        }
         Some(ref description) => {
             let mut is_in_code_block = false;
             let mut text = String::new();
@@ -730,7 +830,7 @@ fn handle_explain(code: &str,
             }
 
             if stdout_isatty() {
-                show_content_with_pager(&text);
+                show_content_with_pager(&text, false);
             } else {
                 print!("{}", text);
             }
@@ -741,7 +841,19 @@ fn handle_explain(code: &str,
     }
 }
 
-fn show_content_with_pager(content: &String) {
+/// Prints the given codes and their explanations as a JSON array
+/// (`--explain-format=json`).
+fn print_explanations_json(explanations: &[(&str, &str)]) {
+    let explanations = explanations.iter().map(|&(code, description)| {
+        let mut obj = BTreeMap::new();
+        obj.insert("code".to_string(), code.to_json());
+        obj.insert("explanation".to_string(), description.trim_left().to_json());
+        Json::Object(obj)
+    }).collect();
+    println!("{}", Json::Array(explanations).pretty());
+}
+
+fn show_content_with_pager(content: &String, color: bool) {
     let pager_name = env::var_os("PAGER").unwrap_or_else(|| if cfg!(windows) {
         OsString::from("more.com")
     } else {
@@ -750,7 +862,14 @@ fn show_content_with_pager(content: &String) {
 
     let mut fallback_to_println = false;
 
-    match Command::new(pager_name).stdin(Stdio::piped()).spawn() {
+    let mut command = Command::new(pager_name);
+    // Make `less` pass the escape codes for colors through, unless the user
+    // configured it otherwise.
+    if color && env::var_os("LESS").is_none() {
+        command.env("LESS", "R");
+    }
+
+    match command.stdin(Stdio::piped()).spawn() {
         Ok(mut pager) => {
             if let Some(pipe) = pager.stdin.as_mut() {
                 if pipe.write_all(content.as_bytes()).is_err() {
@@ -782,8 +901,13 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
                       descriptions: &errors::registry::Registry,
                       output: ErrorOutputType)
                       -> Compilation {
+        let explain_format = parse_explain_format(matches, output);
         if let Some(ref code) = matches.opt_str("explain") {
-            handle_explain(code, descriptions, output);
+            handle_explain(code, descriptions, output, explain_format);
+            return Compilation::Stop;
+        }
+        if explain_format == Some(ExplainFormat::Json) {
+            print_explanations_json(&descriptions.descriptions());
             return Compilation::Stop;
         }
 
@@ -9,12 +9,15 @@
 // except according to those terms.
 
 use rustc::session::Session;
+use rustc::session::config::ProfileQueriesFormat;
 use rustc::util::common::{ProfQDumpParams, ProfileQueriesMsg, profq_msg, profq_set_chan};
+use rustc::hir::def_id::DefId;
 use std::sync::mpsc::{Receiver};
 use std::io::{Write};
 use rustc::dep_graph::{DepNode};
 use std::time::{Duration, Instant};
 
+pub mod self_profile;
 pub mod trace;
 
 /// begin a profile thread, if not already running
@@ -39,6 +42,7 @@ pub fn dump(sess: &Session, path: String) {
         // FIXME: Add another compiler flag to toggle whether this log
         // is written; false for now
         dump_profq_msg_log:true,
+        formats:sess.opts.debugging_opts.profile_queries_format.clone(),
     };
     profq_msg(sess, ProfileQueriesMsg::Dump(params));
     let _ = rx.recv().unwrap();
@@ -56,6 +60,8 @@ enum ParseState {
     HaveTimeBegin(String, Instant),
     // Have "task-begin" information from the last message
     HaveTaskBegin(DepNode, Instant),
+    // Have "trans-item-begin" information from the last message
+    HaveTransItemBegin(DefId, String, Instant),
 }
 struct StackFrame {
     pub parse_st: ParseState,
@@ -68,6 +74,29 @@ fn total_duration(traces: &Vec<trace::Rec>) -> Duration {
     return sum
 }
 
+// write HTML file, and counts file
+fn write_html(path: &str, traces: &Vec<trace::Rec>) {
+    use std::fs::File;
+
+    let html_path = format!("{}.html", path);
+    let mut html_file = File::create(&html_path).unwrap();
+
+    let counts_path = format!("{}.counts.txt", path);
+    let mut counts_file = File::create(&counts_path).unwrap();
+
+    write!(html_file, "<html>\n").unwrap();
+    write!(html_file,
+           "<head>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"{}\">\n",
+           "profile_queries.css").unwrap();
+    write!(html_file, "<style>\n").unwrap();
+    trace::write_style(&mut html_file);
+    write!(html_file, "</style>\n").unwrap();
+    write!(html_file, "</head>\n").unwrap();
+    write!(html_file, "<body>\n").unwrap();
+    trace::write_traces(&mut html_file, &mut counts_file, traces);
+    write!(html_file, "</body>\n</html>\n").unwrap();
+}
+
 // profiling thread; retains state (in local variables) and dump traces, upon request.
 fn profile_queries_thread(r:Receiver<ProfileQueriesMsg>) {
     use self::trace::*;
@@ -102,24 +131,23 @@ fn profile_queries_thread(r:Receiver<ProfileQueriesMsg>) {
                         };
                     }
 
-                    // write HTML file, and counts file
-                    let html_path = format!("{}.html", params.path);
-                    let mut html_file = File::create(&html_path).unwrap();
-
-                    let counts_path = format!("{}.counts.txt", params.path);
-                    let mut counts_file = File::create(&counts_path).unwrap();
-
-                    write!(html_file, "<html>\n").unwrap();
-                    write!(html_file,
-                           "<head>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"{}\">\n",
-                           "profile_queries.css").unwrap();
-                    write!(html_file, "<style>\n").unwrap();
-                    trace::write_style(&mut html_file);
-                    write!(html_file, "</style>\n").unwrap();
-                    write!(html_file, "</head>\n").unwrap();
-                    write!(html_file, "<body>\n").unwrap();
-                    trace::write_traces(&mut html_file, &mut counts_file, &frame.traces);
-                    write!(html_file, "</body>\n</html>\n").unwrap();
+                    for format in &params.formats {
+                        match *format {
+                            ProfileQueriesFormat::Html => {
+                                write_html(&params.path, &frame.traces)
+                            }
+                            ProfileQueriesFormat::ChromeTrace => {
+                                let path = format!("{}.json", params.path);
+                                let mut file = File::create(&path).unwrap();
+                                trace::write_chrome_trace(&mut file, &frame.traces);
+                            }
+                            ProfileQueriesFormat::Folded => {
+                                let path = format!("{}.folded", params.path);
+                                let mut file = File::create(&path).unwrap();
+                                trace::write_folded_stacks(&mut file, &frame.traces);
+                            }
+                        }
+                    }
 
                     let ack_path = format!("{}.ack", params.path);
                     let ack_file = File::create(&ack_path).unwrap();
@@ -130,6 +158,12 @@ fn profile_queries_thread(r:Receiver<ProfileQueriesMsg>) {
                 }
                 continue
             }
+            ProfileQueriesMsg::ItemTimes(sender) => {
+                assert!(stack.len() == 0);
+                assert!(frame.parse_st == ParseState::Clear);
+                sender.send(trace::item_times(&frame.traces)).unwrap();
+                continue
+            }
             // Actual query message:
             msg => {
                 // Record msg in our log
@@ -138,13 +172,14 @@ fn profile_queries_thread(r:Receiver<ProfileQueriesMsg>) {
                 match (frame.parse_st.clone(), msg) {
                     (_,ProfileQueriesMsg::Halt) => unreachable!(),
                     (_,ProfileQueriesMsg::Dump(_)) => unreachable!(),
+                    (_,ProfileQueriesMsg::ItemTimes(_)) => unreachable!(),
 
                     // Parse State: Clear
                     (ParseState::Clear,
-                     ProfileQueriesMsg::QueryBegin(span,querymsg)) => {
+                     ProfileQueriesMsg::QueryBegin(span,querymsg,def_id)) => {
                         let start = Instant::now();
                         frame.parse_st = ParseState::HaveQuery
-                            (Query{span:span, msg:querymsg}, start)
+                            (Query{span:span, msg:querymsg, def_id:def_id}, start)
                     },
                     (ParseState::Clear,
                      ProfileQueriesMsg::CacheHit) => {
@@ -267,6 +302,47 @@ fn profile_queries_thread(r:Receiver<ProfileQueriesMsg>) {
                     },
                     (_, ProfileQueriesMsg::TaskEnd) => { panic!("parse error") }
 
+                    (ParseState::Clear,
+                     ProfileQueriesMsg::TransItemBegin(def_id, item)) => {
+                        let start = Instant::now();
+                        frame.parse_st = ParseState::HaveTransItemBegin(def_id, item, start);
+                        stack.push(frame);
+                        frame = StackFrame{parse_st:ParseState::Clear, traces:vec![]};
+                    },
+                    (_, ProfileQueriesMsg::TransItemBegin(_, _)) =>
+                        panic!("parse error; did not expect trans item begin here"),
+
+                    (ParseState::Clear,
+                     ProfileQueriesMsg::TransItemEnd) => {
+                        let provider_extent = frame.traces;
+                        match stack.pop() {
+                            None =>
+                                panic!("parse error: expected a stack frame; found an empty stack"),
+                            Some(old_frame) => {
+                                match old_frame.parse_st {
+                                    ParseState::HaveTransItemBegin(def_id, item, start) => {
+                                        let duration = start.elapsed();
+                                        frame = StackFrame{
+                                            parse_st:ParseState::Clear,
+                                            traces:old_frame.traces
+                                        };
+                                        let dur_extent = total_duration(&provider_extent);
+                                        let trace = Rec {
+                                            effect: Effect::TransItem(def_id, item),
+                                            extent: Box::new(provider_extent),
+                                            start: start,
+                                            dur_total: duration,
+                                            dur_self: duration - dur_extent,
+                                        };
+                                        frame.traces.push( trace );
+                                    },
+                                    _ => panic!("internal parse error: malformed parse stack")
+                                }
+                            }
+                        }
+                    },
+                    (_, ProfileQueriesMsg::TransItemEnd) => { panic!("parse error") }
+
                     // Parse State: HaveQuery
                     (ParseState::HaveQuery(q,start),
                      ProfileQueriesMsg::CacheHit) => {
@@ -297,10 +373,10 @@ fn profile_queries_thread(r:Receiver<ProfileQueriesMsg>) {
                                 expected something else to follow BeginQuery for {:?}", q)
                     },
                     (ParseState::HaveQuery(q1,_),
-                     ProfileQueriesMsg::QueryBegin(span2,querymsg2)) => {
+                     ProfileQueriesMsg::QueryBegin(span2,querymsg2,def_id2)) => {
                         panic!("parse error: unexpected QueryBegin; \
                                 earlier query is unfinished: {:?} and now {:?}",
-                               q1, Query{span:span2, msg:querymsg2})
+                               q1, Query{span:span2, msg:querymsg2, def_id:def_id2})
                     },
 
                     (ParseState::HaveTimeBegin(_, _), _) => {
@@ -309,6 +385,9 @@ fn profile_queries_thread(r:Receiver<ProfileQueriesMsg>) {
                     (ParseState::HaveTaskBegin(_, _), _) => {
                         unreachable!()
                     },
+                    (ParseState::HaveTransItemBegin(_, _, _), _) => {
+                        unreachable!()
+                    },
                 }
 
             }
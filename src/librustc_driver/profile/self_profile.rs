@@ -0,0 +1,151 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The report of `-Z self-profile`.
+//!
+//! The profile thread attributes the self time of every query to the
+//! definition the key of the query refers to, and the time spent translating
+//! a `TransItem` to LLVM IR to that item, so that every instantiation of a
+//! generic function shows up on its own. The time LLVM spends optimizing and
+//! generating code happens per codegen unit and isn't attributed to any item.
+
+use rustc::hir::def_id::CrateNum;
+use rustc::ty::TyCtxt;
+use rustc::util::common::{ProfQItem, ProfileQueriesMsg, profq_msg, duration_to_secs_str};
+use serialize::json::{self, Json};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+/// Where the machine-readable summary is written.
+const SUMMARY_PATH: &str = "self_profile.json";
+
+/// The time spent on one item.
+struct ItemTime {
+    name: String,
+    /// `"def"` for queries about a definition, `"trans_item"` for translation
+    kind: &'static str,
+    krate: CrateNum,
+    total: Duration,
+    /// The time per query, most expensive first
+    queries: Vec<(String, Duration)>,
+}
+
+/// Prints the most expensive items and crates and writes the time spent on
+/// every item to `self_profile.json`.
+pub fn report(tcx: TyCtxt) {
+    let (tx, rx) = channel();
+    profq_msg(tcx.sess, ProfileQueriesMsg::ItemTimes(tx));
+    let times = rx.recv().unwrap();
+
+    let mut total = Duration::new(0, 0);
+    let mut unattributed = Duration::new(0, 0);
+    let mut by_item: HashMap<ProfQItem, Vec<(String, Duration)>> = HashMap::new();
+    for ((item, query), dur) in times {
+        total += dur;
+        if item == ProfQItem::Other {
+            unattributed += dur;
+        } else {
+            by_item.entry(item).or_insert(vec![]).push((query, dur));
+        }
+    }
+
+    let mut items: Vec<ItemTime> = by_item.into_iter().map(|(item, mut queries)| {
+        queries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let (name, kind, krate) = match item {
+            ProfQItem::Def(def_id) => (tcx.item_path_str(def_id), "def", def_id.krate),
+            ProfQItem::TransItem(def_id, name) => (name, "trans_item", def_id.krate),
+            ProfQItem::Other => unreachable!(),
+        };
+        let total = queries.iter().fold(Duration::new(0, 0), |sum, q| sum + q.1);
+        ItemTime { name, kind, krate, total, queries }
+    }).collect();
+    items.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+
+    let mut by_crate: HashMap<CrateNum, Duration> = HashMap::new();
+    for item in &items {
+        *by_crate.entry(item.krate).or_insert(Duration::new(0, 0)) += item.total;
+    }
+    let mut crates: Vec<(String, Duration)> = by_crate.into_iter().map(|(krate, dur)| {
+        (tcx.crate_name(krate).to_string(), dur)
+    }).collect();
+    crates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let top = tcx.sess.opts.debugging_opts.self_profile_top;
+    println!("self-profile: {}s in queries and translation, {}s of it not spent on any item",
+             duration_to_secs_str(total), duration_to_secs_str(unattributed));
+    println!("");
+    println!("the {} most expensive items:", ::std::cmp::min(top, items.len()));
+    for item in items.iter().take(top) {
+        let (ref query, dur) = item.queries[0];
+        println!("  {}s {:>5}  {}  (most time in {}: {}s)",
+                 duration_to_secs_str(item.total), percent(item.total, total), item.name,
+                 query, duration_to_secs_str(dur));
+    }
+    println!("");
+    println!("time per crate:");
+    for &(ref name, dur) in &crates {
+        println!("  {}s {:>5}  {}", duration_to_secs_str(dur), percent(dur, total), name);
+    }
+
+    write_summary(tcx, total, unattributed, &items, &crates);
+}
+
+fn write_summary(tcx: TyCtxt,
+                 total: Duration,
+                 unattributed: Duration,
+                 items: &[ItemTime],
+                 crates: &[(String, Duration)]) {
+    let items = items.iter().map(|item| {
+        let queries = item.queries.iter().map(|&(ref query, dur)| {
+            (query.clone(), Json::F64(secs(dur)))
+        }).collect::<BTreeMap<_, _>>();
+        let mut obj = BTreeMap::new();
+        obj.insert("item".to_string(), Json::String(item.name.clone()));
+        obj.insert("kind".to_string(), Json::String(item.kind.to_string()));
+        obj.insert("crate".to_string(),
+                   Json::String(tcx.crate_name(item.krate).to_string()));
+        obj.insert("time".to_string(), Json::F64(secs(item.total)));
+        obj.insert("queries".to_string(), Json::Object(queries));
+        Json::Object(obj)
+    }).collect();
+    let crates = crates.iter().map(|&(ref name, dur)| {
+        let mut obj = BTreeMap::new();
+        obj.insert("crate".to_string(), Json::String(name.clone()));
+        obj.insert("time".to_string(), Json::F64(secs(dur)));
+        Json::Object(obj)
+    }).collect();
+
+    let mut summary = BTreeMap::new();
+    summary.insert("total".to_string(), Json::F64(secs(total)));
+    summary.insert("unattributed".to_string(), Json::F64(secs(unattributed)));
+    summary.insert("items".to_string(), Json::Array(items));
+    summary.insert("crates".to_string(), Json::Array(crates));
+
+    let result = File::create(SUMMARY_PATH).and_then(|mut file| {
+        writeln!(file, "{}", json::as_pretty_json(&Json::Object(summary)))
+    });
+    if let Err(e) = result {
+        tcx.sess.err(&format!("could not write `{}`: {}", SUMMARY_PATH, e));
+    }
+}
+
+fn secs(dur: Duration) -> f64 {
+    dur.as_secs() as f64 + dur.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+fn percent(part: Duration, total: Duration) -> String {
+    if total == Duration::new(0, 0) {
+        return String::new();
+    }
+    format!("{:.1}%", secs(part) / secs(total) * 100.0)
+}
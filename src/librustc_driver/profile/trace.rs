@@ -14,17 +14,23 @@ use rustc::ty::maps::QueryMsg;
 use std::fs::File;
 use std::time::{Duration, Instant};
 use std::collections::hash_map::HashMap;
+use std::collections::BTreeMap;
 use rustc::dep_graph::{DepNode};
+use rustc::hir::def_id::DefId;
+use rustc::util::common::{ProfQItem, ProfQItemTimes};
+use serialize::json::Json;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Query {
     pub span: SpanData,
     pub msg: QueryMsg,
+    pub def_id: Option<DefId>,
 }
 pub enum Effect {
     QueryBegin(Query, CacheCase),
     TimeBegin(String),
     TaskBegin(DepNode),
+    TransItem(DefId, String),
 }
 pub enum CacheCase {
     Hit, Miss
@@ -68,6 +74,9 @@ pub fn html_of_effect(eff: &Effect) -> (String, String) {
             let cons = cons_of_key(key);
             (cons.clone(), format!("{} task-begin", cons))
         },
+        Effect::TransItem(_, ref item) => {
+            (item.clone(), format!("trans-item"))
+        },
         Effect::QueryBegin(ref qmsg, ref cc) => {
             let cons = cons_of_query_msg(qmsg);
             (cons.clone(),
@@ -178,6 +187,21 @@ fn compute_counts_rec(counts: &mut HashMap<String,QueryMetric>, traces: &Vec<Rec
                     }};
                 counts.insert(cons, qm);
             },
+            Effect::TransItem(..) => {
+                let qm = match counts.get("trans_item") {
+                    Some(qm) =>
+                        QueryMetric{
+                            count: qm.count + 1,
+                            dur_self: qm.dur_self + t.dur_self,
+                            dur_total: qm.dur_total + t.dur_total,
+                        },
+                    None => QueryMetric{
+                        count: 1,
+                        dur_self: t.dur_self,
+                        dur_total: t.dur_total,
+                    }};
+                counts.insert("trans_item".to_string(), qm);
+            },
             Effect::QueryBegin(ref qmsg, ref _cc) => {
                 let qcons = cons_of_query_msg(qmsg);
                 let qm = match counts.get(&qcons) {
@@ -313,3 +337,135 @@ body {
 }
 ").unwrap();
 }
+
+fn duration_to_micros(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1_000_000.0 + d.subsec_nanos() as f64 / 1_000.0
+}
+
+// Name of a trace in a flamegraph or trace viewer, and the category of the
+// trace for the latter
+fn name_of_effect(eff: &Effect) -> (String, &'static str) {
+    match *eff {
+        Effect::TimeBegin(ref msg) => (msg.clone(), "pass"),
+        Effect::TaskBegin(ref key) => (cons_of_key(key), "task"),
+        Effect::QueryBegin(ref q, _) => (q.msg.name().to_string(), "query"),
+        Effect::TransItem(_, ref item) => (item.clone(), "trans_item"),
+    }
+}
+
+fn write_chrome_trace_rec(file: &mut File, traces: &Vec<Rec>, base: Instant) {
+    for t in traces {
+        let (name, cat) = name_of_effect(&t.effect);
+        let mut args = BTreeMap::new();
+        match t.effect {
+            Effect::QueryBegin(ref q, ref cc) => {
+                if let Some(key) = q.msg.key() {
+                    args.insert("key".to_string(), Json::String(key.to_string()));
+                }
+                let cache = match *cc {
+                    CacheCase::Hit => "hit",
+                    CacheCase::Miss => "miss",
+                };
+                args.insert("cache".to_string(), Json::String(cache.to_string()));
+            }
+            Effect::TaskBegin(ref key) => {
+                args.insert("dep_node".to_string(), Json::String(format!("{:?}", key)));
+            }
+            Effect::TimeBegin(_) | Effect::TransItem(..) => {}
+        }
+
+        let mut event = BTreeMap::new();
+        event.insert("name".to_string(), Json::String(name));
+        event.insert("cat".to_string(), Json::String(cat.to_string()));
+        event.insert("ph".to_string(), Json::String("X".to_string()));
+        event.insert("ts".to_string(), Json::F64(duration_to_micros(t.start - base)));
+        event.insert("dur".to_string(), Json::F64(duration_to_micros(t.dur_total)));
+        event.insert("pid".to_string(), Json::U64(0));
+        event.insert("tid".to_string(), Json::U64(0));
+        event.insert("args".to_string(), Json::Object(args));
+        write!(file, ",\n{}", Json::Object(event)).unwrap();
+
+        write_chrome_trace_rec(file, &t.extent, base);
+    }
+}
+
+/// Writes the traces in Chrome's trace event format, as "complete" events
+/// nested by their timestamps. All queries run on the main thread of the
+/// compiler, which is the only thread in the trace.
+pub fn write_chrome_trace(file: &mut File, traces: &Vec<Rec>) {
+    let base = traces.first().map(|t| t.start).unwrap_or_else(Instant::now);
+    write!(file, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[\n").unwrap();
+    write!(file, "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":0,\"tid\":0,\
+                  \"args\":{{\"name\":\"rustc\"}}}}").unwrap();
+    write_chrome_trace_rec(file, traces, base);
+    write!(file, "\n]}}\n").unwrap();
+}
+
+fn compute_folded_stacks_rec(stacks: &mut BTreeMap<String, u64>,
+                             prefix: &str,
+                             traces: &Vec<Rec>) {
+    for t in traces {
+        let (mut name, _) = name_of_effect(&t.effect);
+        if let Effect::QueryBegin(_, CacheCase::Hit) = t.effect {
+            name.push_str(" (cache hit)");
+        }
+        // `;` separates the frames of a stack
+        let name = name.replace(';', ":");
+        let stack = if prefix.is_empty() {
+            name
+        } else {
+            format!("{};{}", prefix, name)
+        };
+        *stacks.entry(stack.clone()).or_insert(0) += duration_to_micros(t.dur_self) as u64;
+        compute_folded_stacks_rec(stacks, &stack, &t.extent);
+    }
+}
+
+/// Writes the traces as folded stacks, one line per distinct stack of passes,
+/// tasks and queries with the total self time of its innermost frame in
+/// microseconds, as expected by `flamegraph.pl`.
+pub fn write_folded_stacks(file: &mut File, traces: &Vec<Rec>) {
+    let mut stacks = BTreeMap::new();
+    compute_folded_stacks_rec(&mut stacks, "", traces);
+    for (stack, micros) in stacks {
+        if micros > 0 {
+            write!(file, "{} {}\n", stack, micros).unwrap();
+        }
+    }
+}
+
+fn compute_item_times_rec(times: &mut ProfQItemTimes,
+                          parent: &(ProfQItem, String),
+                          traces: &Vec<Rec>) {
+    for t in traces {
+        let attribution = match t.effect {
+            Effect::QueryBegin(ref q, _) => {
+                let item = match q.def_id {
+                    Some(def_id) => ProfQItem::Def(def_id),
+                    None => ProfQItem::Other,
+                };
+                (item, q.msg.name().to_string())
+            }
+            Effect::TransItem(def_id, ref item) => {
+                (ProfQItem::TransItem(def_id, item.clone()), "trans_item".to_string())
+            }
+            // Passes outside of any query are charged to no item in particular.
+            Effect::TimeBegin(ref msg) if parent.0 == ProfQItem::Other => {
+                (ProfQItem::Other, msg.clone())
+            }
+            // Tasks and nested passes do their work on behalf of the
+            // enclosing query (or pass, or trans item).
+            Effect::TimeBegin(_) | Effect::TaskBegin(_) => parent.clone(),
+        };
+        *times.entry(attribution.clone()).or_insert(Duration::new(0, 0)) += t.dur_self;
+        compute_item_times_rec(times, &attribution, &t.extent);
+    }
+}
+
+/// Sums up the self time of every query, pass and trans item, by the item it
+/// is attributed to and the query it was spent in (see `ProfQItemTimes`).
+pub fn item_times(traces: &Vec<Rec>) -> ProfQItemTimes {
+    let mut times = ProfQItemTimes::new();
+    compute_item_times_rec(&mut times, &(ProfQItem::Other, "<other>".to_string()), traces);
+    times
+}
@@ -0,0 +1,229 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Applying code suggestions to source files (`-Z apply-suggestions`).
+//!
+//! Only suggestions that are not approximate and offer a single substitution
+//! are applied, since anything else needs a human to pick the right fix. The
+//! suggestions are applied in the order they were emitted, and a suggestion
+//! touching code that an earlier one already replaced is skipped as a whole.
+
+use CodeSuggestion;
+use CodeMapperDyn;
+
+use rustc_data_structures::sync::Lrc;
+use syntax_pos::FileMap;
+
+use std::fmt::Write;
+
+/// Number of unchanged lines shown around every change in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// A source file with some suggestions applied to it.
+pub struct FixedFile {
+    pub file: Lrc<FileMap>,
+    /// The new contents of the file.
+    pub fixed: String,
+}
+
+/// A replacement of the bytes `lo..hi` of a file with `snippet`.
+#[derive(Clone)]
+struct Replacement {
+    lo: usize,
+    hi: usize,
+    snippet: String,
+}
+
+impl Replacement {
+    fn overlaps(&self, other: &Replacement) -> bool {
+        // Two insertions at the same position conflict as well, since there
+        // is no telling which one should come first.
+        (self.lo < other.hi && other.lo < self.hi) || self.lo == other.lo
+    }
+}
+
+/// Applies all applicable, non-overlapping `suggestions` and returns the
+/// files that changed, in the order they were first touched.
+pub fn apply_suggestions(suggestions: &[CodeSuggestion], cm: &CodeMapperDyn) -> Vec<FixedFile> {
+    let mut files: Vec<(Lrc<FileMap>, Vec<Replacement>)> = vec![];
+
+    'suggestions: for suggestion in suggestions {
+        if suggestion.approximate || suggestion.substitutions.len() != 1 {
+            continue;
+        }
+
+        let mut replacements = vec![];
+        for part in &suggestion.substitutions[0].parts {
+            let file = cm.lookup_char_pos(part.span.lo()).file;
+            if file.src.is_none() || part.span.hi() > file.end_pos {
+                continue 'suggestions;
+            }
+            let replacement = Replacement {
+                lo: (part.span.lo().0 - file.start_pos.0) as usize,
+                hi: (part.span.hi().0 - file.start_pos.0) as usize,
+                snippet: part.snippet.clone(),
+            };
+            let conflict = files.iter()
+                .filter(|&&(ref f, _)| f.start_pos == file.start_pos)
+                .flat_map(|&(_, ref rs)| rs.iter())
+                .chain(replacements.iter()
+                           .filter(|&&(ref f, _)| f.start_pos == file.start_pos)
+                           .map(|&(_, ref r)| r))
+                .any(|r| r.overlaps(&replacement));
+            if conflict {
+                continue 'suggestions;
+            }
+            replacements.push((file, replacement));
+        }
+
+        for (file, replacement) in replacements {
+            match files.iter().position(|&(ref f, _)| f.start_pos == file.start_pos) {
+                Some(i) => files[i].1.push(replacement),
+                None => files.push((file, vec![replacement])),
+            }
+        }
+    }
+
+    files.into_iter().map(|(file, mut replacements)| {
+        replacements.sort_by_key(|r| (r.lo, r.hi));
+        let fixed = {
+            let src = file.src.as_ref().unwrap();
+            let mut fixed = String::with_capacity(src.len());
+            let mut pos = 0;
+            for r in &replacements {
+                fixed.push_str(&src[pos..r.lo]);
+                fixed.push_str(&r.snippet);
+                pos = r.hi;
+            }
+            fixed.push_str(&src[pos..]);
+            fixed
+        };
+        FixedFile { file, fixed }
+    }).collect()
+}
+
+/// Renders the changes made to a file as a unified diff, with the name of the
+/// file as both the old and the new path so that it applies with `patch -p0`.
+pub fn unified_diff(fixed: &FixedFile) -> String {
+    let old = lines(fixed.file.src.as_ref().unwrap());
+    let new = lines(&fixed.fixed);
+
+    // The files only differ in a few places, so rather than computing a
+    // minimal line diff, every change simply extends to the next line both
+    // files have in common.
+    let mut out = String::new();
+    let name = fixed.file.name.to_string();
+    writeln!(out, "--- {}", name).unwrap();
+    writeln!(out, "+++ {}", name).unwrap();
+
+    let mut hunks: Vec<Hunk> = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        // Find the end of the change by looking for the next line both
+        // files have in common.
+        let (end_i, end_j) = resync(&old, &new, i, j);
+        let merge = hunks.last().map_or(false, |last| last.old_end + 2 * DIFF_CONTEXT >= i);
+        if merge {
+            let last = hunks.last_mut().unwrap();
+            last.old_end = end_i;
+            last.new_end = end_j;
+        } else {
+            hunks.push(Hunk { old_start: i, old_end: end_i, new_start: j, new_end: end_j });
+        }
+        i = end_i;
+        j = end_j;
+    }
+
+    for hunk in hunks {
+        let before = ::std::cmp::min(DIFF_CONTEXT, hunk.old_start);
+        let after = ::std::cmp::min(DIFF_CONTEXT, old.len() - hunk.old_end);
+        let old_start = hunk.old_start - before;
+        let new_start = hunk.new_start - before;
+        let old_len = hunk.old_end + after - old_start;
+        let new_len = hunk.new_end + after - new_start;
+        writeln!(out, "@@ -{},{} +{},{} @@",
+                 old_start + 1, old_len, new_start + 1, new_len).unwrap();
+
+        // Unchanged lines between changes that were merged into this hunk
+        // show up on both sides, so they are written as context.
+        let (mut i, mut j) = (old_start, new_start);
+        while i < old_start + old_len || j < new_start + new_len {
+            if i < old_start + old_len && j < new_start + new_len && old[i] == new[j] {
+                write_line(&mut out, ' ', old[i]);
+                i += 1;
+                j += 1;
+                continue;
+            }
+            let (end_i, end_j) = resync(&old, &new, i, j);
+            for line in &old[i..end_i] {
+                write_line(&mut out, '-', line);
+            }
+            for line in &new[j..end_j] {
+                write_line(&mut out, '+', line);
+            }
+            i = end_i;
+            j = end_j;
+        }
+    }
+
+    out
+}
+
+struct Hunk {
+    old_start: usize,
+    old_end: usize,
+    new_start: usize,
+    new_end: usize,
+}
+
+/// Given that `old[i]` and `new[j]` differ, returns the first pair of
+/// positions from which both files continue with the same line, preferring
+/// the smallest change.
+fn resync(old: &[&str], new: &[&str], i: usize, j: usize) -> (usize, usize) {
+    let max = (old.len() - i) + (new.len() - j);
+    for total in 1..max {
+        for di in 0..total + 1 {
+            let dj = total - di;
+            let (i2, j2) = (i + di, j + dj);
+            if i2 < old.len() && j2 < new.len() && old[i2] == new[j2] {
+                return (i2, j2);
+            }
+        }
+    }
+    (old.len(), new.len())
+}
+
+/// Splits `s` into lines, keeping their line terminators.
+fn lines(s: &str) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut start = 0;
+    for (i, _) in s.match_indices('\n') {
+        lines.push(&s[start..i + 1]);
+        start = i + 1;
+    }
+    if start < s.len() {
+        lines.push(&s[start..]);
+    }
+    lines
+}
+
+fn write_line(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
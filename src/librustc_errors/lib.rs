@@ -53,6 +53,8 @@ use termcolor::{ColorSpec, Color};
 mod diagnostic;
 mod diagnostic_builder;
 pub mod emitter;
+pub mod apply;
+pub mod markdown;
 mod snippet;
 pub mod registry;
 mod styled_buffer;
@@ -280,6 +282,10 @@ pub struct Handler {
     // this handler. These hashes is used to avoid emitting the same error
     // twice.
     emitted_diagnostics: RefCell<FxHashSet<u128>>,
+
+    // The suggestions of all emitted diagnostics, if `collect_suggestions`
+    // is set.
+    suggestions: RefCell<Vec<CodeSuggestion>>,
 }
 
 #[derive(Default)]
@@ -287,6 +293,9 @@ pub struct HandlerFlags {
     pub can_emit_warnings: bool,
     pub treat_err_as_bug: bool,
     pub external_macro_backtrace: bool,
+    /// Keep the suggestions of emitted diagnostics, to be applied at the end
+    /// of the compilation.
+    pub collect_suggestions: bool,
 }
 
 impl Handler {
@@ -336,6 +345,7 @@ impl Handler {
             tracked_diagnostics: RefCell::new(None),
             tracked_diagnostic_codes: RefCell::new(FxHashSet()),
             emitted_diagnostics: RefCell::new(FxHashSet()),
+            suggestions: RefCell::new(Vec::new()),
         }
     }
 
@@ -673,8 +683,16 @@ impl Handler {
             if db.is_error() {
                 self.bump_err_count();
             }
+            if self.flags.collect_suggestions {
+                self.suggestions.borrow_mut().extend(db.suggestions.iter().cloned());
+            }
         }
     }
+
+    /// Takes the suggestions collected so far, see `HandlerFlags::collect_suggestions`.
+    pub fn take_suggestions(&self) -> Vec<CodeSuggestion> {
+        mem::replace(&mut *self.suggestions.borrow_mut(), Vec::new())
+    }
 }
 
 
@@ -0,0 +1,140 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rendering of the markdown of long error code explanations for terminals,
+//! as done by `rustc --explain`.
+//!
+//! This only understands the small subset of markdown the explanations in
+//! the registry use: headings, fenced code blocks and inline code. Code
+//! blocks lose their fences, are indented and get some basic highlighting of
+//! Rust syntax, and lines hidden from rustdoc with `# ` are left out.
+
+use std::io::{self, Write};
+use termcolor::{Buffer, ColorSpec, Color, WriteColor};
+
+const KEYWORDS: &[&str] = &[
+    "as", "box", "break", "const", "continue", "crate", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Writes `text` to `dst`, using colors to render the markdown if `dst`
+/// supports them.
+pub fn render_markdown(text: &str, dst: &mut WriteColor) -> io::Result<()> {
+    let mut is_in_code_block = false;
+
+    for line in text.lines() {
+        let dedented_line = line.trim_left();
+        if dedented_line.starts_with("```") {
+            is_in_code_block = !is_in_code_block;
+            continue;
+        }
+
+        if is_in_code_block {
+            // Lines hidden in the documentation are hidden here as well.
+            if dedented_line.starts_with("# ") || dedented_line == "#" {
+                continue;
+            }
+            write!(dst, "    ")?;
+            write_code(line, dst)?;
+        } else if line.starts_with('#') {
+            let heading = line.trim_left_matches('#').trim();
+            write_styled(dst, heading, ColorSpec::new().set_bold(true).set_intense(true))?;
+        } else {
+            write_inline(line, dst)?;
+        }
+        writeln!(dst, "")?;
+    }
+
+    Ok(())
+}
+
+/// Renders `text` into a string, using ANSI escape codes for colors if
+/// `color` is set.
+pub fn render_to_string(text: &str, color: bool) -> String {
+    let mut buffer = if color { Buffer::ansi() } else { Buffer::no_color() };
+    // Writing to memory cannot fail.
+    render_markdown(text, &mut buffer).unwrap();
+    String::from_utf8_lossy(buffer.as_slice()).into_owned()
+}
+
+/// Writes a line of prose, highlighting inline code.
+fn write_inline(line: &str, dst: &mut WriteColor) -> io::Result<()> {
+    let mut spec = ColorSpec::new();
+    spec.set_fg(Some(Color::Cyan));
+
+    for (i, part) in line.split('`').enumerate() {
+        if i % 2 == 1 {
+            write_styled(dst, part, &spec)?;
+        } else {
+            write!(dst, "{}", part)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a line of Rust code, highlighting comments, string literals and
+/// keywords.
+fn write_code(line: &str, dst: &mut WriteColor) -> io::Result<()> {
+    let mut comment = ColorSpec::new();
+    comment.set_fg(Some(Color::Green));
+    let mut string = ColorSpec::new();
+    string.set_fg(Some(Color::Yellow));
+    let mut keyword = ColorSpec::new();
+    keyword.set_fg(Some(Color::Magenta)).set_bold(true);
+
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("//") {
+            write_styled(dst, rest, &comment)?;
+            rest.len()
+        } else if c == '"' {
+            let len = string_literal_len(rest);
+            write_styled(dst, &rest[..len], &string)?;
+            len
+        } else if c.is_alphanumeric() || c == '_' {
+            let len = rest.find(|c: char| !c.is_alphanumeric() && c != '_')
+                          .unwrap_or(rest.len());
+            let word = &rest[..len];
+            if KEYWORDS.contains(&word) {
+                write_styled(dst, word, &keyword)?;
+            } else {
+                write!(dst, "{}", word)?;
+            }
+            len
+        } else {
+            write!(dst, "{}", c)?;
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    Ok(())
+}
+
+/// Returns the length of the string literal `s` starts with, or the length of
+/// `s` if the literal continues on the next line.
+fn string_literal_len(s: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return i + 1,
+            _ => escaped = false,
+        }
+    }
+    s.len()
+}
+
+fn write_styled(dst: &mut WriteColor, text: &str, spec: &ColorSpec) -> io::Result<()> {
+    dst.set_color(spec)?;
+    write!(dst, "{}", text)?;
+    dst.reset()
+}
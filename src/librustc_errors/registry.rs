@@ -23,4 +23,13 @@ impl Registry {
     pub fn find_description(&self, code: &str) -> Option<&'static str> {
         self.descriptions.get(code).cloned()
     }
+
+    /// All registered codes and their descriptions, sorted by code.
+    pub fn descriptions(&self) -> Vec<(&'static str, &'static str)> {
+        let mut descriptions = self.descriptions.iter()
+                                                .map(|(&code, &desc)| (code, desc))
+                                                .collect::<Vec<_>>();
+        descriptions.sort();
+        descriptions
+    }
 }
@@ -54,6 +54,7 @@ use rustc::hir;
 use rustc::hir::intravisit::{self, NestedVisitorMap, Visitor};
 use rustc::ich::{ATTR_IF_THIS_CHANGED, ATTR_THEN_THIS_WOULD_NEED};
 use graphviz::IntoCow;
+use std::collections::VecDeque;
 use std::env;
 use std::fs::{self, File};
 use std::io::Write;
@@ -66,6 +67,10 @@ pub fn assert_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
             dump_graph(tcx);
         }
 
+        if let Some(ref filter) = tcx.sess.opts.debugging_opts.explain_dep {
+            explain_dep(tcx, filter);
+        }
+
         // if the `rustc_attrs` feature is not enabled, then the
         // attributes we are interested in cannot be present anyway, so
         // skip the walk.
@@ -264,6 +269,76 @@ fn dump_graph(tcx: TyCtxt) {
     }
 }
 
+/// The most paths `-Z explain-dep` prints.
+const MAX_EXPLAINED_PATHS: usize = 10;
+
+/// Prints the shortest paths along which a change to a node matching the
+/// source of `filter` reaches nodes matching its target, i.e. why the
+/// targets would have to be recomputed if the source changed.
+fn explain_dep(tcx: TyCtxt, filter: &str) {
+    let edge_filter = match EdgeFilter::new(filter) {
+        Ok(edge_filter) => edge_filter,
+        Err(e) => {
+            tcx.sess.err(&format!("invalid `-Z explain-dep` filter: {}", e));
+            return;
+        }
+    };
+    let query = tcx.dep_graph.query();
+    let graph = &query.graph;
+
+    // A breadth-first search from all sources at once, going from every node
+    // to the nodes that read it, finds the shortest path to every target.
+    let mut parent: Vec<Option<NodeIndex>> = vec![None; graph.len_nodes()];
+    let mut visited = vec![false; graph.len_nodes()];
+    let mut queue = VecDeque::new();
+    let mut source_count = 0;
+    for (index, node) in graph.all_nodes().iter().enumerate() {
+        if edge_filter.source.test(&node.data) {
+            visited[index] = true;
+            queue.push_back(NodeIndex(index));
+            source_count += 1;
+        }
+    }
+
+    let mut reached = vec![];
+    while let Some(index) = queue.pop_front() {
+        for reader in graph.predecessor_nodes(index) {
+            if visited[reader.node_id()] {
+                continue;
+            }
+            visited[reader.node_id()] = true;
+            parent[reader.node_id()] = Some(index);
+            queue.push_back(reader);
+            if edge_filter.target.test(graph.node_data(reader)) {
+                reached.push(reader);
+            }
+        }
+    }
+
+    println!("explain-dep: {} nodes match `{}`, {} of the nodes depending on them match `{}`",
+             source_count, edge_filter.source.text(), reached.len(),
+             edge_filter.target.text());
+
+    // `reached` is ordered by the length of the paths already.
+    for &target in reached.iter().take(MAX_EXPLAINED_PATHS) {
+        let mut path = vec![target];
+        while let Some(index) = parent[path.last().unwrap().node_id()] {
+            path.push(index);
+        }
+        println!("");
+        println!("{:?} depends on {:?}:",
+                 graph.node_data(target), graph.node_data(*path.last().unwrap()));
+        for (i, &index) in path.iter().rev().enumerate() {
+            let arrow = if i == 0 { "  " } else { "->" };
+            println!("    {} {:?}", arrow, graph.node_data(index));
+        }
+    }
+    if reached.len() > MAX_EXPLAINED_PATHS {
+        println!("");
+        println!("... and {} more", reached.len() - MAX_EXPLAINED_PATHS);
+    }
+}
+
 pub struct GraphvizDepGraph<'q>(FxHashSet<&'q DepNode>,
                                 Vec<(&'q DepNode, &'q DepNode)>);
 
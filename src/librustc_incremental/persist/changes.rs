@@ -0,0 +1,141 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Explains why queries were re-executed in an incremental build
+//! (`-Z incremental-changes`).
+//!
+//! The dep-graph of the previous session, as loaded by `load_dep_graph`,
+//! holds the fingerprint of every input and every query result, along with
+//! the inputs and queries each query read. Diffing those fingerprints against
+//! the ones of the current session tells which inputs changed. Walking the
+//! edges backwards from the changed inputs then finds the queries that could
+//! not be marked green and had to be re-executed because of them. A query
+//! that is re-executed but produces the same result as before stops the
+//! walk, since its dependents can be marked green again.
+
+use rustc::dep_graph::{DepNode, DepNodeColor, SerializedDepNodeIndex};
+use rustc::ich::Fingerprint;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use std::collections::VecDeque;
+
+/// Why a node of the previous dep-graph was re-executed.
+struct Reexecution {
+    /// The changed input or query that made it impossible to mark the node
+    /// green
+    cause: SerializedDepNodeIndex,
+    /// Whether the query produced a different result than last time
+    changed: bool,
+}
+
+pub fn report_changes<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
+    let prev = match tcx.dep_graph.previous_graph() {
+        Some(prev) => prev,
+        None => return,
+    };
+
+    let current = tcx.dep_graph.serialize();
+    let current_fingerprints: FxHashMap<DepNode, Fingerprint> =
+        current.nodes.iter().cloned().collect();
+
+    // The edges of the previous dep-graph, reversed: which nodes read each node.
+    let node_count = prev.node_count();
+    let mut readers: IndexVec<SerializedDepNodeIndex, Vec<SerializedDepNodeIndex>> =
+        IndexVec::from_elem_n(vec![], node_count);
+    for index in (0..node_count).map(SerializedDepNodeIndex::new) {
+        for &target in prev.edge_targets_from(index) {
+            readers[target].push(index);
+        }
+    }
+
+    println!("[incremental]");
+    println!("[incremental] Changes since the previous compilation session");
+    println!("[incremental]");
+
+    let mut queue = VecDeque::new();
+    for index in (0..node_count).map(SerializedDepNodeIndex::new) {
+        let node = prev.index_to_node(index);
+        if !node.kind.is_input() {
+            continue
+        }
+        match current_fingerprints.get(&node) {
+            Some(&fingerprint) if fingerprint == prev.fingerprint_by_index(index) => {}
+            Some(_) => {
+                println!("[incremental] changed input: {:?}", node);
+                queue.push_back(index);
+            }
+            None => {
+                println!("[incremental] removed input: {:?}", node);
+                queue.push_back(index);
+            }
+        }
+    }
+
+    let mut new_inputs = 0;
+    let mut new_queries = 0;
+    for &(node, _) in current.nodes.iter() {
+        if prev.node_to_index_opt(&node).is_some() {
+            continue
+        }
+        if node.kind.is_input() {
+            println!("[incremental] new input: {:?}", node);
+            new_inputs += 1;
+        } else {
+            new_queries += 1;
+        }
+    }
+
+    if queue.is_empty() && new_inputs == 0 {
+        println!("[incremental] no inputs changed");
+    }
+
+    // A query that read a changed input (or the changed result of another
+    // query) could not be marked green, so if it has a color at all, it was
+    // re-executed.
+    let mut reexecuted: FxHashMap<SerializedDepNodeIndex, Reexecution> = FxHashMap();
+    let mut order = vec![];
+    while let Some(index) = queue.pop_front() {
+        for &reader in &readers[index] {
+            if reexecuted.contains_key(&reader) {
+                continue
+            }
+            let changed = match tcx.dep_graph.node_color(&prev.index_to_node(reader)) {
+                Some(DepNodeColor::Red) => true,
+                Some(DepNodeColor::Green(_)) => false,
+                // Not needed in this session (or gone).
+                None => continue,
+            };
+            reexecuted.insert(reader, Reexecution { cause: index, changed });
+            order.push(reader);
+            if changed {
+                queue.push_back(reader);
+            }
+        }
+    }
+
+    if !order.is_empty() {
+        let unchanged = order.iter().filter(|index| !reexecuted[*index].changed).count();
+        println!("[incremental]");
+        println!("[incremental] {} queries re-executed because of changed inputs, \
+                  {} of which produced the same result as before:",
+                 order.len(), unchanged);
+        for index in order {
+            let reexecution = &reexecuted[&index];
+            println!("[incremental]   {:?}{} <- {:?}",
+                     prev.index_to_node(index),
+                     if reexecution.changed { "" } else { " (unchanged)" },
+                     prev.index_to_node(reexecution.cause));
+        }
+    }
+
+    println!("[incremental]");
+    println!("[incremental] {} queries executed for the first time", new_queries);
+}
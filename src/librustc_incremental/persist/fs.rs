@@ -61,6 +61,26 @@
 //! any more and will delete those. It will also delete any finalized session
 //! directories for a given crate except for the most recent one.
 //!
+//! ## Size Limit
+//!
+//! Garbage collection only ever looks at the directory of the crate being
+//! compiled, so the sessions of crates that aren't compiled any more stay
+//! around forever. With `-C incremental-max-size`, the compiler additionally
+//! makes sure that the whole incremental compilation directory, shared by all
+//! crates, doesn't grow beyond the given size: After publishing its session
+//! directory, it deletes any files in finalized session directories that no
+//! work product refers to any more, and then deletes the least recently used
+//! finalized session directories of any crate until the directory is small
+//! enough again. A session directory is used whenever its crate is compiled,
+//! which always creates a new session directory, so the least recently used
+//! session directories are the ones with the oldest timestamps.
+//! Only one compiler process at a time does this, synchronized via a lock file
+//! in the incremental compilation directory. Other processes just skip this
+//! step, since the process holding the lock will take care of their session
+//! directories as well. Session directories themselves are locked exactly as
+//! during garbage collection, so nothing that is still being read from is
+//! deleted.
+//!
 //! ## Synchronization
 //!
 //! There is some synchronization needed in order for the compiler to be able to
@@ -119,6 +139,8 @@ use rustc::session::{Session, CrateDisambiguator};
 use rustc::util::fs as fs_util;
 use rustc_data_structures::{flock, base_n};
 use rustc_data_structures::fx::{FxHashSet, FxHashMap};
+use rustc_serialize::Decodable;
+use rustc_serialize::opaque::Decoder;
 
 use std::fs as std_fs;
 use std::io;
@@ -128,10 +150,14 @@ use std::time::{UNIX_EPOCH, SystemTime, Duration};
 
 use rand::{thread_rng, Rng};
 
+use super::data::SerializedWorkProduct;
+use super::file_format;
+
 const LOCK_FILE_EXT: &'static str = ".lock";
 const DEP_GRAPH_FILENAME: &'static str = "dep-graph.bin";
 const WORK_PRODUCTS_FILENAME: &'static str = "work-products.bin";
 const QUERY_CACHE_FILENAME: &'static str = "query-cache.bin";
+const SIZE_LIMIT_LOCK_FILENAME: &'static str = "size-limit.lock";
 
 // We encode integers using the following base, so they are shorter than decimal
 // or hexadecimal numbers (we want short file and directory names). Since these
@@ -384,6 +410,7 @@ pub fn finalize_session_directory(sess: &Session, svh: Svh) {
     }
 
     let _ = garbage_collect_session_directories(sess);
+    let _ = enforce_size_limit(sess);
 }
 
 pub fn delete_all_session_dir_contents(sess: &Session) -> io::Result<()> {
@@ -863,6 +890,217 @@ pub fn garbage_collect_session_directories(sess: &Session) -> io::Result<()> {
     Ok(())
 }
 
+/// A finalized session directory that might be evicted to enforce the size
+/// limit of the incremental compilation directory.
+#[derive(Debug, PartialEq)]
+struct EvictionCandidate {
+    timestamp: SystemTime,
+    path: PathBuf,
+    /// The size of the files in the directory that are not hard-linked into
+    /// a more recent session directory
+    size: u64,
+}
+
+/// Deletes unused files and the least recently used session directories of
+/// all crates until the incremental compilation directory is no larger than
+/// `-C incremental-max-size`. See the module documentation for details.
+pub fn enforce_size_limit(sess: &Session) -> io::Result<()> {
+    let max_size = match sess.opts.cg.incremental_max_size {
+        Some(max_size) => max_size,
+        None => return Ok(()),
+    };
+    let incr_dir = sess.opts.incremental.as_ref().unwrap();
+
+    debug!("enforce_size_limit() - begin");
+
+    let lock_file_path = incr_dir.join(SIZE_LIMIT_LOCK_FILENAME);
+    let _lock = match flock::Lock::new(&lock_file_path,
+                                       false,  // don't wait
+                                       true,   // create the lock-file
+                                       true) { // get an exclusive lock
+        Ok(lock) => lock,
+        Err(_) => {
+            debug!("enforce_size_limit() - another process is already at it");
+            return Ok(())
+        }
+    };
+
+    // Collect the session directories of all crates, newest first, so that
+    // files hard-linked into several of them count for the newest one.
+    let mut session_dirs = vec![];
+    for crate_dir in try!(incr_dir.read_dir()) {
+        let crate_dir = match crate_dir {
+            Ok(crate_dir) => crate_dir.path(),
+            Err(_) => continue,
+        };
+        let entries = match crate_dir.read_dir() {
+            Ok(entries) => entries,
+            // This is something we don't know, leave it alone
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let directory_name = entry.file_name().to_string_lossy().into_owned();
+            if !is_session_directory(&directory_name) {
+                continue
+            }
+            if let Ok(timestamp) = extract_timestamp_from_session_dir(&directory_name) {
+                session_dirs.push((timestamp, entry.path(), is_finalized(&directory_name)));
+            }
+        }
+    }
+    session_dirs.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let current_session_dir = sess.incr_comp_session_dir().clone();
+    let mut seen_files = FxHashSet();
+    let mut total_size = 0;
+    let mut candidates = vec![];
+    let mut files_deleted = 0;
+    for (timestamp, path, finalized) in session_dirs {
+        let can_be_evicted = finalized && path != current_session_dir;
+        if can_be_evicted {
+            files_deleted += delete_unused_files(&path);
+        }
+        let size = session_dir_size(&path, &mut seen_files);
+        total_size += size;
+        if can_be_evicted {
+            candidates.push(EvictionCandidate { timestamp, path, size });
+        }
+    }
+
+    let mut dirs_deleted = 0;
+    let mut bytes_freed = 0;
+    for candidate in least_recently_used_first(candidates) {
+        if total_size <= max_size {
+            break
+        }
+
+        let lock_file_path = lock_file_path(&candidate.path);
+        let lock = match flock::Lock::new(&lock_file_path,
+                                          false,  // don't wait
+                                          false,  // don't create the lock-file
+                                          true) { // get an exclusive lock
+            Ok(lock) => lock,
+            Err(_) => {
+                debug!("enforce_size_limit() - not evicting `{}`, still in use",
+                       candidate.path.display());
+                continue
+            }
+        };
+
+        debug!("enforce_size_limit() - evicting `{}`", candidate.path.display());
+        if let Err(err) = safe_remove_dir_all(&candidate.path) {
+            sess.warn(&format!("Failed to evict incremental compilation session \
+                                directory `{}`: {}",
+                               candidate.path.display(),
+                               err));
+        } else {
+            delete_session_dir_lock_file(sess, &lock_file_path);
+            total_size -= candidate.size;
+            bytes_freed += candidate.size;
+            dirs_deleted += 1;
+        }
+
+        mem::drop(lock);
+    }
+
+    if sess.opts.debugging_opts.incremental_info {
+        println!("[incremental] size limit: deleted {} unused files and \
+                  {} session directories, freeing {} bytes; {} bytes in use",
+                 files_deleted, dirs_deleted, bytes_freed, total_size);
+    }
+
+    Ok(())
+}
+
+fn least_recently_used_first(mut candidates: Vec<EvictionCandidate>) -> Vec<EvictionCandidate> {
+    candidates.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    candidates
+}
+
+/// Deletes the files in a finalized session directory that no work product
+/// refers to any more, returning how many were deleted. Directories that are
+/// in use, or whose work products can't be read (for example because they
+/// were written by a different version of the compiler), are left alone.
+fn delete_unused_files(session_dir: &Path) -> usize {
+    let lock_file_path = lock_file_path(session_dir);
+    let _lock = match flock::Lock::new(&lock_file_path,
+                                       false,  // don't wait
+                                       false,  // don't create the lock-file
+                                       true) { // get an exclusive lock
+        Ok(lock) => lock,
+        Err(_) => return 0,
+    };
+
+    let work_products_path = in_incr_comp_dir(session_dir, WORK_PRODUCTS_FILENAME);
+    let (bytes, start_pos) = match file_format::read_file(false, &work_products_path) {
+        Ok(Some(data_and_pos)) => data_and_pos,
+        _ => return 0,
+    };
+    let mut decoder = Decoder::new(&bytes, start_pos);
+    let work_products: Vec<SerializedWorkProduct> = match Decodable::decode(&mut decoder) {
+        Ok(work_products) => work_products,
+        Err(_) => return 0,
+    };
+
+    let mut used_files: FxHashSet<String> = work_products.into_iter()
+        .flat_map(|swp| swp.work_product.saved_files.into_iter().map(|(_, name)| name))
+        .collect();
+    used_files.insert(DEP_GRAPH_FILENAME.to_string());
+    used_files.insert(WORK_PRODUCTS_FILENAME.to_string());
+    used_files.insert(QUERY_CACHE_FILENAME.to_string());
+
+    let entries = match session_dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    let mut deleted = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if !used_files.contains(&file_name) {
+            debug!("delete_unused_files() - deleting `{}`", entry.path().display());
+            if safe_remove_file(&entry.path()).is_ok() {
+                deleted += 1;
+            }
+        }
+    }
+    deleted
+}
+
+/// Sums up the sizes of the files in a session directory, skipping files
+/// that are hard links to files already seen.
+fn session_dir_size(session_dir: &Path, seen_files: &mut FxHashSet<(u64, u64)>) -> u64 {
+    let entries = match session_dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    let mut size = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let first_link = match file_id(&metadata) {
+            Some(id) => seen_files.insert(id),
+            None => true,
+        };
+        if first_link {
+            size += metadata.len();
+        }
+    }
+    size
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std_fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_: &std_fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
 fn all_except_most_recent(deletion_candidates: Vec<(SystemTime, PathBuf, Option<flock::Lock>)>)
                           -> FxHashMap<PathBuf, Option<flock::Lock>> {
     let most_recent = deletion_candidates.iter()
@@ -928,6 +1166,22 @@ fn test_all_except_most_recent() {
     );
 }
 
+#[test]
+fn test_least_recently_used_first() {
+    let candidate = |secs, name| EvictionCandidate {
+        timestamp: UNIX_EPOCH + Duration::new(secs, 0),
+        path: PathBuf::from(name),
+        size: 1,
+    };
+
+    assert_eq!(least_recently_used_first(vec![candidate(3, "3"),
+                                              candidate(1, "1"),
+                                              candidate(2, "2")]),
+               vec![candidate(1, "1"), candidate(2, "2"), candidate(3, "3")]);
+
+    assert_eq!(least_recently_used_first(vec![]), vec![]);
+}
+
 #[test]
 fn test_timestamp_serialization() {
     for i in 0 .. 1_000u64 {
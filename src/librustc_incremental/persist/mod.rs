@@ -12,6 +12,7 @@
 //! into the given directory. At the same time, it also hashes the
 //! various HIR nodes.
 
+mod changes;
 mod data;
 mod dirty_clean;
 mod fs;
@@ -19,6 +19,7 @@ use std::io::{self, Cursor};
 use std::fs;
 use std::path::PathBuf;
 
+use super::changes;
 use super::data::*;
 use super::fs::*;
 use super::dirty_clean;
@@ -33,6 +34,10 @@ pub fn save_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
             return;
         }
 
+        if sess.opts.debugging_opts.incremental_changes {
+            changes::report_changes(tcx);
+        }
+
         time(sess, "persist query result cache", || {
             save_in(sess,
                     query_cache_path(sess),
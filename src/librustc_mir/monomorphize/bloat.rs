@@ -0,0 +1,131 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The report of `-Z print-mono-bloat`: the collected mono items are grouped
+//! by the definition they instantiate, and every group lists how large its
+//! instantiations are and which mono items required them.
+//!
+//! Sizes are given in MIR statements, as estimated by the partitioner, and in
+//! LLVM instructions, as counted while translating. The latter only include
+//! instances translated in this session, and count every copy of an instance
+//! that is inlined into several codegen units.
+
+use monomorphize::collector::InliningMap;
+use monomorphize::item::MonoItemExt;
+use rustc::hir::def_id::DefId;
+use rustc::mir::mono::MonoItem;
+use rustc::ty::{Instance, TyCtxt};
+use rustc::util::nodemap::{FxHashMap, FxHashSet};
+
+use std::cmp::Ordering;
+
+/// The size of the instantiations of one generic definition.
+struct Group {
+    name: String,
+    mir_size: usize,
+    llvm_insns: usize,
+    instances: Vec<InstanceSize>,
+}
+
+struct InstanceSize {
+    name: String,
+    mir_size: usize,
+    llvm_insns: usize,
+    /// The mono items whose bodies refer to the instance
+    callers: Vec<String>,
+}
+
+/// Largest first, and by name if the sizes are equal.
+fn by_size(a: (usize, usize, &str), b: (usize, usize, &str)) -> Ordering {
+    b.0.cmp(&a.0).then_with(|| b.1.cmp(&a.1)).then_with(|| a.2.cmp(b.2))
+}
+
+/// Prints the generic definitions with the largest instantiations.
+/// `instance_insns` are the LLVM instructions translated for each symbol.
+pub fn print_report<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                              items: &FxHashSet<MonoItem<'tcx>>,
+                              inlining_map: &InliningMap<'tcx>,
+                              instance_insns: &[(String, usize)]) {
+    let mut llvm_insns: FxHashMap<&str, usize> = FxHashMap();
+    for &(ref symbol_name, insns) in instance_insns {
+        *llvm_insns.entry(&symbol_name[..]).or_insert(0) += insns;
+    }
+
+    let mut callers: FxHashMap<MonoItem<'tcx>, Vec<MonoItem<'tcx>>> = FxHashMap();
+    inlining_map.iter_accesses(|accessor, accessees| {
+        for accessee in accessees {
+            callers.entry(*accessee).or_insert(vec![]).push(accessor);
+        }
+    });
+
+    let mut by_def_id: FxHashMap<DefId, Vec<Instance<'tcx>>> = FxHashMap();
+    for item in items {
+        if let MonoItem::Fn(instance) = *item {
+            by_def_id.entry(instance.def_id()).or_insert(vec![]).push(instance);
+        }
+    }
+
+    let mut groups: Vec<Group> = by_def_id.into_iter().filter(|&(_, ref instances)| {
+        instances.iter().any(|instance| instance.substs.types().next().is_some())
+    }).map(|(def_id, instances)| {
+        let mut instances: Vec<InstanceSize> = instances.into_iter().map(|instance| {
+            let item = MonoItem::Fn(instance);
+            let symbol_name = tcx.symbol_name(instance);
+            let mut item_callers: Vec<String> = callers.get(&item).map_or(vec![], |callers| {
+                callers.iter().map(|caller| caller.to_string(tcx)).collect()
+            });
+            item_callers.sort();
+            item_callers.dedup();
+            InstanceSize {
+                name: item.to_string(tcx),
+                mir_size: item.size_estimate(&tcx),
+                llvm_insns: llvm_insns.get(&*symbol_name).cloned().unwrap_or(0),
+                callers: item_callers,
+            }
+        }).collect();
+        instances.sort_by(|a, b| {
+            by_size((a.llvm_insns, a.mir_size, &a.name), (b.llvm_insns, b.mir_size, &b.name))
+        });
+        Group {
+            name: tcx.item_path_str(def_id),
+            mir_size: instances.iter().map(|instance| instance.mir_size).sum(),
+            llvm_insns: instances.iter().map(|instance| instance.llvm_insns).sum(),
+            instances,
+        }
+    }).collect();
+    groups.sort_by(|a, b| {
+        by_size((a.llvm_insns, a.mir_size, &a.name), (b.llvm_insns, b.mir_size, &b.name))
+    });
+
+    let instantiations: usize = groups.iter().map(|group| group.instances.len()).sum();
+    println!("mono-bloat: {} instantiations of {} generic definitions, \
+              {} MIR statements, {} LLVM instructions",
+             instantiations,
+             groups.len(),
+             groups.iter().map(|group| group.mir_size).sum::<usize>(),
+             groups.iter().map(|group| group.llvm_insns).sum::<usize>());
+
+    let top = tcx.sess.opts.debugging_opts.mono_bloat_top;
+    println!("");
+    println!("the {} largest generic definitions:", ::std::cmp::min(top, groups.len()));
+    for group in groups.iter().take(top) {
+        println!("  {} ({} instantiations, {} MIR statements, {} LLVM instructions)",
+                 group.name, group.instances.len(), group.mir_size, group.llvm_insns);
+        for instance in &group.instances {
+            println!("    {} ({} MIR statements, {} LLVM instructions)",
+                     instance.name, instance.mir_size, instance.llvm_insns);
+            if instance.callers.is_empty() {
+                println!("      collected as a root");
+            } else {
+                println!("      instantiated by {}", instance.callers.join(", "));
+            }
+        }
+    }
+}
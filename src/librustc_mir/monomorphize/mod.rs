@@ -18,6 +18,7 @@ use rustc::ty::{self, Ty, TyCtxt};
 pub use rustc::ty::Instance;
 pub use self::item::{MonoItem, MonoItemExt};
 
+pub mod bloat;
 pub mod collector;
 pub mod item;
 pub mod partitioning;
@@ -111,7 +111,11 @@ use rustc::middle::exported_symbols::SymbolExportLevel;
 use rustc::ty::{self, TyCtxt, InstanceDef};
 use rustc::ty::item_path::characteristic_def_id_of_type;
 use rustc::util::nodemap::{FxHashMap, FxHashSet};
+use rustc_serialize::json::{self, Json};
+use std::collections::BTreeMap;
 use std::collections::hash_map::Entry;
+use std::fs::File;
+use std::io::Write;
 use syntax::ast::NodeId;
 use syntax::symbol::{Symbol, InternedString};
 use rustc::mir::mono::MonoItem;
@@ -234,8 +238,11 @@ pub fn partition<'a, 'tcx, I>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
     // If the partitioning should produce a fixed count of codegen units, merge
     // until that count is reached.
+    let mut merged_cgus = FxHashMap();
     if let PartitioningStrategy::FixedUnitCount(count) = strategy {
-        merge_codegen_units(&mut initial_partitioning, count, &tcx.crate_name.as_str());
+        merged_cgus = merge_codegen_units(&mut initial_partitioning,
+                                          count,
+                                          &tcx.crate_name.as_str());
 
         debug_dump(tcx, "POST MERGING:", initial_partitioning.codegen_units.iter());
     }
@@ -268,6 +275,10 @@ pub fn partition<'a, 'tcx, I>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         cgu1.name().cmp(cgu2.name())
     });
 
+    if let Some(ref path) = tcx.sess.opts.debugging_opts.print_cgu_partitioning {
+        write_partitioning_report(tcx, path, &result, &merged_cgus);
+    }
+
     result
 }
 
@@ -431,9 +442,12 @@ fn place_root_translation_items<'a, 'tcx, I>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     }
 }
 
+/// Returns the names of the initial codegen units that were merged into each
+/// of the resulting ones.
 fn merge_codegen_units<'tcx>(initial_partitioning: &mut PreInliningPartitioning<'tcx>,
                              target_cgu_count: usize,
-                             crate_name: &str) {
+                             crate_name: &str)
+                             -> FxHashMap<InternedString, Vec<InternedString>> {
     assert!(target_cgu_count >= 1);
     let codegen_units = &mut initial_partitioning.codegen_units;
 
@@ -448,6 +462,9 @@ fn merge_codegen_units<'tcx>(initial_partitioning: &mut PreInliningPartitioning<
     // the stable sort below will keep everything nice and deterministic.
     codegen_units.sort_by_key(|cgu| cgu.name().clone());
 
+    let mut cgu_contents: FxHashMap<InternedString, Vec<InternedString>> =
+        codegen_units.iter().map(|cgu| (cgu.name().clone(), vec![cgu.name().clone()])).collect();
+
     // Merge the two smallest codegen units until the target size is reached.
     while codegen_units.len() > target_cgu_count {
         // Sort small cgus to the back
@@ -459,11 +476,19 @@ fn merge_codegen_units<'tcx>(initial_partitioning: &mut PreInliningPartitioning<
         for (k, v) in smallest.items_mut().drain() {
             second_smallest.items_mut().insert(k, v);
         }
+
+        let mut consumed = cgu_contents.remove(smallest.name()).unwrap();
+        cgu_contents.get_mut(second_smallest.name()).unwrap().append(&mut consumed);
     }
 
+    let mut merged_cgus = FxHashMap();
     for (index, cgu) in codegen_units.iter_mut().enumerate() {
+        let mut contents = cgu_contents.remove(cgu.name()).unwrap();
+        contents.sort();
         cgu.set_name(numbered_codegen_unit_name(crate_name, index));
+        merged_cgus.insert(cgu.name().clone(), contents);
     }
+    merged_cgus
 }
 
 fn place_inlined_translation_items<'tcx>(initial_partitioning: PreInliningPartitioning<'tcx>,
@@ -731,3 +756,90 @@ fn debug_dump<'a, 'b, 'tcx, I>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         }
     }
 }
+
+/// Writes the final partitioning to `path` as JSON (`-Z print-cgu-partitioning`).
+/// Every codegen unit lists its root items, which only exist in that unit, and
+/// the items copied into it because some root refers to them. An inlined item
+/// is a `local-copy` if it is defined in this crate and `external` if it is an
+/// instantiation of an upstream definition.
+fn write_partitioning_report<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                       path: &str,
+                                       codegen_units: &[CodegenUnit<'tcx>],
+                                       merged_cgus: &FxHashMap<InternedString,
+                                                               Vec<InternedString>>) {
+    // The number of codegen units each item has been placed in
+    let mut copies = FxHashMap();
+    for cgu in codegen_units {
+        for trans_item in cgu.items().keys() {
+            *copies.entry(*trans_item).or_insert(0u64) += 1;
+        }
+    }
+
+    let cgus = codegen_units.iter().map(|cgu| {
+        let mut items: Vec<_> = cgu.items().iter().map(|(&trans_item, &linkage)| {
+            (trans_item.to_string(tcx), trans_item, linkage)
+        }).collect();
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut roots = vec![];
+        let mut inlined = vec![];
+        let mut inlined_size = 0;
+        for (name, trans_item, (linkage, visibility)) in items {
+            let size_estimate = trans_item.size_estimate(&tcx);
+            let characteristic_def_id = characteristic_def_id_of_trans_item(tcx, trans_item);
+
+            let mut obj = BTreeMap::new();
+            obj.insert("item".to_string(), Json::String(name));
+            obj.insert("symbol".to_string(),
+                       Json::String(trans_item.symbol_name(tcx).to_string()));
+            obj.insert("size_estimate".to_string(), Json::U64(size_estimate as u64));
+            obj.insert("linkage".to_string(), Json::String(format!("{:?}", linkage)));
+            obj.insert("visibility".to_string(), Json::String(format!("{:?}", visibility)));
+
+            match trans_item.instantiation_mode(tcx) {
+                InstantiationMode::GloballyShared { .. } => {
+                    // The definition whose module decided the home of the item
+                    let placed_by = match characteristic_def_id {
+                        Some(def_id) => Json::String(tcx.item_path_str(def_id)),
+                        None => Json::Null,
+                    };
+                    obj.insert("placed_by".to_string(), placed_by);
+                    roots.push(Json::Object(obj));
+                }
+                InstantiationMode::LocalCopy => {
+                    let is_local = characteristic_def_id.map_or(false, |def_id| def_id.is_local());
+                    let reason = if is_local { "local-copy" } else { "external" };
+                    obj.insert("reason".to_string(), Json::String(reason.to_string()));
+                    obj.insert("copies".to_string(), Json::U64(copies[&trans_item]));
+                    inlined_size += size_estimate;
+                    inlined.push(Json::Object(obj));
+                }
+            }
+        }
+
+        let merged_from = match merged_cgus.get(cgu.name()) {
+            Some(names) => names.iter().map(|name| Json::String(name.to_string())).collect(),
+            None => vec![],
+        };
+
+        let mut obj = BTreeMap::new();
+        obj.insert("name".to_string(), Json::String(cgu.name().to_string()));
+        obj.insert("size_estimate".to_string(), Json::U64(cgu.size_estimate() as u64));
+        obj.insert("inlined_size_estimate".to_string(), Json::U64(inlined_size as u64));
+        obj.insert("merged_from".to_string(), Json::Array(merged_from));
+        obj.insert("roots".to_string(), Json::Array(roots));
+        obj.insert("inlined".to_string(), Json::Array(inlined));
+        Json::Object(obj)
+    }).collect();
+
+    let mut report = BTreeMap::new();
+    report.insert("crate".to_string(), Json::String(tcx.crate_name.to_string()));
+    report.insert("codegen_units".to_string(), Json::Array(cgus));
+
+    let result = File::create(path).and_then(|mut file| {
+        writeln!(file, "{}", json::as_pretty_json(&Json::Object(report)))
+    });
+    if let Err(e) = result {
+        tcx.sess.err(&format!("could not write `{}`: {}", path, e));
+    }
+}
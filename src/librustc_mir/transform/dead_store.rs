@@ -0,0 +1,167 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Dead store elimination.
+//!
+//! This removes assignments to locals that are not live afterwards, i.e. that
+//! are neither read nor dropped before they are assigned again or go out of
+//! scope. For example,
+//!
+//!     _2 = _1;
+//!     _2 = const 5u32;
+//!     _0 = _2;
+//!
+//! becomes
+//!
+//!     nop;
+//!     _2 = const 5u32;
+//!     _0 = _2;
+//!
+//! Removing a store can make the stores of the values it used dead, so the
+//! analysis is repeated until nothing changes. Liveness does not see accesses
+//! through references (see `util::liveness`), so locals that are borrowed
+//! anywhere in the body are left alone. Assignments that allocate, or that move
+//! out values which need to be dropped, are kept as well.
+
+use rustc::mir::*;
+use rustc::mir::visit::Visitor;
+use rustc::ty::{self, TyCtxt};
+use transform::{MirPass, MirSource};
+use util::liveness::{self, LivenessMode, LocalSet};
+
+pub struct DeadStoreElimination;
+
+impl MirPass for DeadStoreElimination {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        // We only run when the MIR optimization level is > 1.
+        // Removing assignments to user variables messes up debug info.
+        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
+            return;
+        }
+
+        let param_env = tcx.param_env(source.def_id);
+        let borrowed = borrowed_locals(mir);
+        let mode = LivenessMode {
+            include_regular_use: true,
+            include_drops: true,
+        };
+
+        loop {
+            let dead_stores = find_dead_stores(tcx, param_env, mir, &borrowed, mode);
+            if dead_stores.is_empty() {
+                break
+            }
+            for location in dead_stores {
+                let statement = &mut mir[location.block].statements[location.statement_index];
+                debug!("removing dead store {:?}", statement);
+                statement.make_nop();
+            }
+        }
+    }
+}
+
+fn find_dead_stores<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                              param_env: ty::ParamEnv<'tcx>,
+                              mir: &Mir<'tcx>,
+                              borrowed: &LocalSet,
+                              mode: LivenessMode)
+                              -> Vec<Location> {
+    let liveness = liveness::liveness_of_locals(mir, mode);
+    let mut dead_stores = vec![];
+    for block in mir.basic_blocks().indices() {
+        // `simulate_block` walks backwards and gives us the locals live on
+        // entry to each location, which are the ones live after the
+        // previous statement.
+        let mut live_after: Option<LocalSet> = None;
+        liveness.simulate_block(mir, block, |location, live| {
+            if let Some(ref live_after) = live_after {
+                let statement = &mir[location.block].statements[location.statement_index];
+                if let StatementKind::Assign(Place::Local(local), ref rvalue) = statement.kind {
+                    if local != RETURN_PLACE &&
+                       !live_after.contains(&local) &&
+                       !borrowed.contains(&local) &&
+                       !has_side_effects(tcx, param_env, mir, rvalue) {
+                        dead_stores.push(location);
+                    }
+                }
+            }
+            live_after = Some(live.clone());
+        });
+    }
+    dead_stores
+}
+
+/// Whether removing the assignment of `rvalue` would change more than the
+/// value of the assigned local.
+fn has_side_effects<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                              param_env: ty::ParamEnv<'tcx>,
+                              mir: &Mir<'tcx>,
+                              rvalue: &Rvalue<'tcx>)
+                              -> bool {
+    // Moving out of a place that needs to be dropped hands over the
+    // responsibility to drop it, which we must not lose.
+    let moves_drop = |operand: &Operand<'tcx>| {
+        match *operand {
+            Operand::Move(ref place) => {
+                place.ty(mir, tcx).to_ty(tcx).needs_drop(tcx, param_env)
+            }
+            Operand::Copy(_) | Operand::Constant(_) => false,
+        }
+    };
+
+    match *rvalue {
+        Rvalue::NullaryOp(NullOp::Box, _) => true,
+        Rvalue::Use(ref operand) |
+        Rvalue::Repeat(ref operand, _) |
+        Rvalue::Cast(_, ref operand, _) |
+        Rvalue::UnaryOp(_, ref operand) => moves_drop(operand),
+        Rvalue::BinaryOp(_, ref lhs, ref rhs) |
+        Rvalue::CheckedBinaryOp(_, ref lhs, ref rhs) => moves_drop(lhs) || moves_drop(rhs),
+        Rvalue::Aggregate(_, ref operands) => operands.iter().any(moves_drop),
+        Rvalue::NullaryOp(NullOp::SizeOf, _) |
+        Rvalue::Ref(..) |
+        Rvalue::Len(_) |
+        Rvalue::Discriminant(_) => false,
+    }
+}
+
+/// The locals of which any part is borrowed somewhere in `mir`.
+fn borrowed_locals(mir: &Mir) -> LocalSet {
+    let mut visitor = BorrowedLocalsVisitor {
+        borrowed: LocalSet::new_empty(mir.local_decls.len()),
+    };
+    visitor.visit_mir(mir);
+    visitor.borrowed
+}
+
+struct BorrowedLocalsVisitor {
+    borrowed: LocalSet,
+}
+
+impl<'tcx> Visitor<'tcx> for BorrowedLocalsVisitor {
+    fn visit_rvalue(&mut self,
+                    rvalue: &Rvalue<'tcx>,
+                    location: Location) {
+        if let Rvalue::Ref(_, _, ref place) = *rvalue {
+            let mut place = place;
+            while let Place::Projection(ref proj) = *place {
+                place = &proj.base;
+            }
+            if let Place::Local(local) = *place {
+                self.borrowed.add(&local);
+            }
+        }
+
+        self.super_rvalue(rvalue, location)
+    }
+}
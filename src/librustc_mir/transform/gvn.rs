@@ -0,0 +1,335 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Global value numbering.
+//!
+//! This finds pure rvalues that compute a value which has already been
+//! computed at a dominating location, and replaces them with a copy of the
+//! local holding that value:
+//!
+//!     _3 = Len(_1);
+//!     ...
+//!     _7 = Len(_1);
+//!
+//! becomes
+//!
+//!     _3 = Len(_1);
+//!     ...
+//!     _7 = _3;
+//!
+//! Only locals in SSA form take part: locals that are assigned exactly once
+//! (or never, for arguments), and are never borrowed or mutated through a
+//! projection, so their value is the same wherever they can be used. Copies
+//! between such locals are looked through, so `_4 = _2; _5 = Add(_4, _1)` and
+//! `_6 = _2; _8 = Add(_6, _1)` compute the same value.
+//!
+//! The blocks are visited in a preorder of the dominator tree, with the
+//! expressions available in a block being those computed in the blocks that
+//! dominate it. Locals whose value is reused lose their storage markers, as
+//! the reuse can be outside of the range they delimit.
+
+use rustc::hir;
+use rustc::mir::*;
+use rustc::mir::visit::{PlaceContext, Visitor};
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc::util::nodemap::FxHashMap;
+use rustc_data_structures::control_flow_graph::dominators::Dominators;
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::IndexVec;
+use syntax_pos::DUMMY_SP;
+use transform::{MirPass, MirSource};
+
+pub struct GlobalValueNumbering;
+
+impl MirPass for GlobalValueNumbering {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        // Don't run on constant MIR, because trans might not be able to
+        // evaluate the modified MIR.
+        // FIXME(eddyb) Remove check after miri is merged.
+        let id = tcx.hir.as_local_node_id(source.def_id).unwrap();
+        match (tcx.hir.body_owner_kind(id), source.promoted) {
+            (_, Some(_)) |
+            (hir::BodyOwnerKind::Const, _) |
+            (hir::BodyOwnerKind::Static(_), _) => return,
+
+            (hir::BodyOwnerKind::Fn, _) => {
+                if tcx.is_const_fn(source.def_id) {
+                    // Don't run on const functions, as, again, trans might not be able to evaluate
+                    // the optimized IR.
+                    return
+                }
+            }
+        }
+
+        // We only run when the MIR optimization level is > 1.
+        // This avoids a slow pass.
+        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
+            return;
+        }
+
+        let (replacements, reused) = {
+            let mut numbering = ValueNumbering {
+                tcx,
+                param_env: tcx.param_env(source.def_id),
+                mir: &*mir,
+                ssa_locals: ssa_locals(mir),
+                values: FxHashMap(),
+                expressions: FxHashMap(),
+                replacements: vec![],
+                reused: IdxSetBuf::new_empty(mir.local_decls.len()),
+            };
+            numbering.visit_dominator_tree(&mir.dominators());
+            (numbering.replacements, numbering.reused)
+        };
+
+        for (location, local) in replacements {
+            debug!("replacing the rvalue at {:?} with {:?}", location, local);
+            let statement = &mut mir[location.block].statements[location.statement_index];
+            if let StatementKind::Assign(_, ref mut rvalue) = statement.kind {
+                *rvalue = Rvalue::Use(Operand::Copy(Place::Local(local)));
+            }
+        }
+
+        for block in mir.basic_blocks_mut() {
+            for statement in &mut block.statements {
+                let marks_reused_local = match statement.kind {
+                    StatementKind::StorageLive(local) |
+                    StatementKind::StorageDead(local) => reused.contains(&local),
+                    _ => false,
+                };
+                if marks_reused_local {
+                    statement.make_nop();
+                }
+            }
+        }
+    }
+}
+
+/// An operand of an expression.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Value<'tcx> {
+    /// The value of an SSA local
+    Local(Local),
+    Constant(Ty<'tcx>, Literal<'tcx>),
+}
+
+/// A pure rvalue in terms of the values of its operands.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Expr<'tcx> {
+    Binary(BinOp, Value<'tcx>, Value<'tcx>),
+    CheckedBinary(BinOp, Value<'tcx>, Value<'tcx>),
+    Unary(UnOp, Value<'tcx>),
+    Cast(CastKind, Value<'tcx>, Ty<'tcx>),
+    Len(Value<'tcx>),
+    /// The length of the slice or array a pointer points to
+    LenOfDeref(Value<'tcx>),
+    Discriminant(Value<'tcx>),
+    SizeOf(Ty<'tcx>),
+}
+
+struct ValueNumbering<'b, 'a: 'b, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    mir: &'b Mir<'tcx>,
+    ssa_locals: IdxSetBuf<Local>,
+    /// The values of SSA locals that are copies of other values
+    values: FxHashMap<Local, Value<'tcx>>,
+    /// The SSA locals holding the expressions available at the current block
+    expressions: FxHashMap<Expr<'tcx>, Local>,
+    /// The rvalues that can be replaced with a copy of a local
+    replacements: Vec<(Location, Local)>,
+    /// The locals copied by the replacements
+    reused: IdxSetBuf<Local>,
+}
+
+impl<'b, 'a, 'tcx> ValueNumbering<'b, 'a, 'tcx> {
+    fn visit_dominator_tree(&mut self, dominators: &Dominators<BasicBlock>) {
+        let mut children = IndexVec::from_elem(vec![], self.mir.basic_blocks());
+        for block in self.mir.basic_blocks().indices() {
+            if block != START_BLOCK && dominators.is_reachable(block) {
+                children[dominators.immediate_dominator(block)].push(block);
+            }
+        }
+
+        // The expressions added by each block on the path from the root are
+        // forgotten again once all blocks dominated by it have been visited.
+        let mut stack = vec![(START_BLOCK, None)];
+        while let Some((block, added)) = stack.pop() {
+            match added {
+                Some(added) => {
+                    for expr in added {
+                        self.expressions.remove(&expr);
+                    }
+                }
+                None => {
+                    let added = self.visit_block(block);
+                    stack.push((block, Some(added)));
+                    for &child in children[block].iter().rev() {
+                        stack.push((child, None));
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_block(&mut self, block: BasicBlock) -> Vec<Expr<'tcx>> {
+        let mut added = vec![];
+        let mir = self.mir;
+        for (statement_index, statement) in mir[block].statements.iter().enumerate() {
+            let (place, rvalue) = match statement.kind {
+                StatementKind::Assign(ref place, ref rvalue) => (place, rvalue),
+                _ => continue,
+            };
+            let ssa_local = match *place {
+                Place::Local(local) if self.ssa_locals.contains(&local) => Some(local),
+                _ => None,
+            };
+
+            if let Rvalue::Use(ref operand) = *rvalue {
+                if let (Some(local), Some(value)) = (ssa_local, self.operand_value(operand)) {
+                    self.values.insert(local, value);
+                }
+                continue
+            }
+
+            let expr = match self.expr(rvalue) {
+                Some(expr) => expr,
+                None => continue,
+            };
+            if let Some(&available) = self.expressions.get(&expr) {
+                let location = Location { block, statement_index };
+                self.replacements.push((location, available));
+                self.reused.add(&available);
+                if let Some(local) = ssa_local {
+                    self.values.insert(local, Value::Local(available));
+                }
+            } else if let Some(local) = ssa_local {
+                // Only values that can be copied may be used more than once.
+                let ty = mir.local_decls[local].ty;
+                if !ty.moves_by_default(self.tcx, self.param_env, DUMMY_SP) {
+                    self.expressions.insert(expr.clone(), local);
+                    added.push(expr);
+                }
+            }
+        }
+        added
+    }
+
+    fn local_value(&self, local: Local) -> Option<Value<'tcx>> {
+        if !self.ssa_locals.contains(&local) {
+            return None;
+        }
+        Some(self.values.get(&local).cloned().unwrap_or(Value::Local(local)))
+    }
+
+    fn operand_value(&self, operand: &Operand<'tcx>) -> Option<Value<'tcx>> {
+        match *operand {
+            Operand::Copy(Place::Local(local)) |
+            Operand::Move(Place::Local(local)) => self.local_value(local),
+            Operand::Constant(ref constant) => {
+                Some(Value::Constant(constant.ty, constant.literal.clone()))
+            }
+            Operand::Copy(_) | Operand::Move(_) => None,
+        }
+    }
+
+    fn expr(&self, rvalue: &Rvalue<'tcx>) -> Option<Expr<'tcx>> {
+        Some(match *rvalue {
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) => {
+                Expr::Binary(op, self.operand_value(lhs)?, self.operand_value(rhs)?)
+            }
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) => {
+                Expr::CheckedBinary(op, self.operand_value(lhs)?, self.operand_value(rhs)?)
+            }
+            Rvalue::UnaryOp(op, ref operand) => Expr::Unary(op, self.operand_value(operand)?),
+            Rvalue::Cast(kind, ref operand, ty) => {
+                Expr::Cast(kind, self.operand_value(operand)?, ty)
+            }
+            Rvalue::Len(Place::Local(local)) => Expr::Len(self.local_value(local)?),
+            Rvalue::Len(Place::Projection(ref proj)) => {
+                match (&proj.base, &proj.elem) {
+                    (&Place::Local(local), &ProjectionElem::Deref) => {
+                        Expr::LenOfDeref(self.local_value(local)?)
+                    }
+                    _ => return None,
+                }
+            }
+            Rvalue::Discriminant(Place::Local(local)) => {
+                Expr::Discriminant(self.local_value(local)?)
+            }
+            Rvalue::NullaryOp(NullOp::SizeOf, ty) => Expr::SizeOf(ty),
+            Rvalue::Use(_) |
+            Rvalue::Repeat(..) |
+            Rvalue::Ref(..) |
+            Rvalue::Len(_) |
+            Rvalue::Discriminant(_) |
+            Rvalue::NullaryOp(NullOp::Box, _) |
+            Rvalue::Aggregate(..) => return None,
+        })
+    }
+}
+
+/// Finds the locals that are assigned exactly once, or never for arguments,
+/// and are only read otherwise.
+fn ssa_locals(mir: &Mir) -> IdxSetBuf<Local> {
+    let mut visitor = SsaVisitor {
+        defs: IndexVec::from_elem(0, &mir.local_decls),
+        not_ssa: IdxSetBuf::new_empty(mir.local_decls.len()),
+    };
+    visitor.visit_mir(mir);
+
+    let mut ssa_locals = IdxSetBuf::new_empty(mir.local_decls.len());
+    for local in mir.local_decls.indices() {
+        let defs = match mir.local_kind(local) {
+            LocalKind::ReturnPointer => continue,
+            LocalKind::Arg => 0,
+            LocalKind::Var | LocalKind::Temp => 1,
+        };
+        if visitor.defs[local] == defs && !visitor.not_ssa.contains(&local) {
+            ssa_locals.add(&local);
+        }
+    }
+    ssa_locals
+}
+
+struct SsaVisitor {
+    defs: IndexVec<Local, usize>,
+    not_ssa: IdxSetBuf<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for SsaVisitor {
+    fn visit_local(&mut self,
+                   &local: &Local,
+                   context: PlaceContext<'tcx>,
+                   _location: Location) {
+        match context {
+            PlaceContext::Store |
+            PlaceContext::Call => self.defs[local] += 1,
+
+            PlaceContext::Inspect |
+            PlaceContext::Copy |
+            PlaceContext::Move |
+            PlaceContext::Projection(Mutability::Not) |
+            PlaceContext::StorageLive |
+            PlaceContext::StorageDead |
+            PlaceContext::Validate => {}
+
+            PlaceContext::AsmOutput |
+            PlaceContext::Drop |
+            PlaceContext::Borrow { .. } |
+            PlaceContext::Projection(Mutability::Mut) => {
+                self.not_ssa.add(&local);
+            }
+        }
+    }
+}
@@ -9,6 +9,11 @@
 // except according to those terms.
 
 //! Inlining pass for MIR functions
+//!
+//! Inlining is done when optimizing, or when `-Z inline-mir` asks for it.
+//! Callees from other crates are inlined if their MIR is in the crate
+//! metadata, i.e. if they are generic or `#[inline]`. `-Z print-mir-inlining`
+//! prints the decision taken for every call site.
 
 use rustc::hir;
 use rustc::hir::TransFnAttrFlags;
@@ -21,7 +26,10 @@ use rustc::mir::*;
 use rustc::mir::visit::*;
 use rustc::ty::{self, Instance, Ty, TyCtxt};
 use rustc::ty::subst::{Subst,Substs};
+use rustc::session::Session;
+use rustc::session::config::OptLevel;
 
+use std::cmp;
 use std::collections::VecDeque;
 use std::iter;
 use transform::{MirPass, MirSource};
@@ -34,6 +42,7 @@ const DEFAULT_THRESHOLD: usize = 50;
 const HINT_THRESHOLD: usize = 100;
 
 const INSTR_COST: usize = 5;
+const CHEAP_INSTR_COST: usize = 1;
 const CALL_PENALTY: usize = 25;
 
 const UNKNOWN_SIZE_COST: usize = 10;
@@ -53,9 +62,61 @@ impl MirPass for Inline {
                           tcx: TyCtxt<'a, 'tcx, 'tcx>,
                           source: MirSource,
                           mir: &mut Mir<'tcx>) {
-        if tcx.sess.opts.debugging_opts.mir_opt_level >= 2 {
-            Inliner { tcx, source }.run_pass(mir);
+        if !inlining_enabled(tcx.sess) {
+            return;
+        }
+
+        // Don't inline into const functions, as trans might not be able to
+        // evaluate the inlined bodies.
+        if tcx.is_const_fn(source.def_id) {
+            return;
         }
+
+        Inliner { tcx, source }.run_pass(mir);
+    }
+}
+
+/// Inlining is done when optimizing, unless `-Z inline-mir` says otherwise.
+fn inlining_enabled(sess: &Session) -> bool {
+    let debugging_opts = &sess.opts.debugging_opts;
+    match debugging_opts.inline_mir {
+        Some(enabled) => enabled,
+        None => {
+            debugging_opts.mir_opt_level >= 2 ||
+                (debugging_opts.mir_opt_level >= 1 && sess.opts.optimize != OptLevel::No)
+        }
+    }
+}
+
+fn statement_cost(kind: &StatementKind) -> usize {
+    match *kind {
+        StatementKind::Assign(_, ref rvalue) => rvalue_cost(rvalue),
+        StatementKind::SetDiscriminant { .. } => INSTR_COST,
+        StatementKind::InlineAsm { .. } => CALL_PENALTY,
+        StatementKind::StorageLive(_) |
+        StatementKind::StorageDead(_) |
+        StatementKind::Validate(..) |
+        StatementKind::EndRegion(_) |
+        StatementKind::UserAssertTy(..) |
+        StatementKind::Nop => 0,
+    }
+}
+
+fn rvalue_cost(rvalue: &Rvalue) -> usize {
+    match *rvalue {
+        // Moves, copies and casts are mostly optimized away by LLVM.
+        Rvalue::Use(_) |
+        Rvalue::Ref(..) |
+        Rvalue::Cast(..) |
+        Rvalue::Len(_) |
+        Rvalue::Discriminant(_) |
+        Rvalue::NullaryOp(NullOp::SizeOf, _) => CHEAP_INSTR_COST,
+        Rvalue::Aggregate(_, ref operands) => CHEAP_INSTR_COST * cmp::max(operands.len(), 1),
+        Rvalue::Repeat(..) |
+        Rvalue::BinaryOp(..) |
+        Rvalue::UnaryOp(..) => INSTR_COST,
+        Rvalue::CheckedBinaryOp(..) => 2 * INSTR_COST,
+        Rvalue::NullaryOp(NullOp::Box, _) => CALL_PENALTY,
     }
 }
 
@@ -96,13 +157,12 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                 if let TerminatorKind::Call {
                     func: Operand::Constant(ref f), .. } = terminator.kind {
                         if let ty::TyFnDef(callee_def_id, substs) = f.ty.sty {
-                            if let Some(instance) = Instance::resolve(self.tcx,
-                                                                      param_env,
-                                                                      callee_def_id,
-                                                                      substs) {
+                            if let Some((callee, substs)) = self.resolve_callee(param_env,
+                                                                                callee_def_id,
+                                                                                substs) {
                                 callsites.push_back(CallSite {
-                                    callee: instance.def_id(),
-                                    substs: instance.substs,
+                                    callee,
+                                    substs,
                                     bb,
                                     location: terminator.source_info
                                 });
@@ -122,25 +182,35 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
             while let Some(callsite) = callsites.pop_front() {
                 debug!("checking whether to inline callsite {:?}", callsite);
                 if !self.tcx.is_mir_available(callsite.callee) {
-                    debug!("checking whether to inline callsite {:?} - MIR unavailable", callsite);
+                    let reason = self.mir_unavailable_reason(callsite.callee);
+                    self.explain(callsite, Err(&reason[..]));
                     continue;
                 }
 
-                let callee_mir = match ty::queries::optimized_mir::try_get(self.tcx,
-                                                                           callsite.location.span,
-                                                                           callsite.callee) {
-                    Ok(callee_mir) if self.should_inline(callsite, callee_mir) => {
-                        self.tcx.subst_and_normalize_erasing_regions(
-                            &callsite.substs,
-                            param_env,
-                            callee_mir,
-                        )
-                    }
-                    Ok(_) => continue,
+                let (callee_mir, reason) = match ty::queries::optimized_mir::try_get(
+                    self.tcx,
+                    callsite.location.span,
+                    callsite.callee,
+                ) {
+                    Ok(callee_mir) => match self.should_inline(callsite, callee_mir) {
+                        Ok(reason) => {
+                            let callee_mir = self.tcx.subst_and_normalize_erasing_regions(
+                                &callsite.substs,
+                                param_env,
+                                callee_mir,
+                            );
+                            (callee_mir, reason)
+                        }
+                        Err(reason) => {
+                            self.explain(callsite, Err(&reason[..]));
+                            continue
+                        }
+                    },
 
                     Err(mut bug) => {
                         // FIXME(#43542) shouldn't have to cancel an error
                         bug.cancel();
+                        self.explain(callsite, Err("the callee's MIR depends on the caller"));
                         continue
                     }
                 };
@@ -148,10 +218,10 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                 let start = caller_mir.basic_blocks().len();
                 debug!("attempting to inline callsite {:?} - mir={:?}", callsite, callee_mir);
                 if !self.inline_call(callsite, caller_mir, callee_mir) {
-                    debug!("attempting to inline callsite {:?} - failure", callsite);
+                    self.explain(callsite, Err("diverging calls are not inlined"));
                     continue;
                 }
-                debug!("attempting to inline callsite {:?} - success", callsite);
+                self.explain(callsite, Ok(&reason[..]));
 
                 // Add callsites from inlined function
                 for (bb, bb_data) in caller_mir.basic_blocks().iter_enumerated().skip(start) {
@@ -160,10 +230,18 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     if let TerminatorKind::Call {
                         func: Operand::Constant(ref f), .. } = terminator.kind {
                         if let ty::TyFnDef(callee_def_id, substs) = f.ty.sty {
+                            // Resolve trait methods of the inlined body, which
+                            // may be known now that its substs are.
+                            let (callee, substs) = match self.resolve_callee(param_env,
+                                                                             callee_def_id,
+                                                                             substs) {
+                                Some(resolved) => resolved,
+                                None => continue,
+                            };
                             // Don't inline the same function multiple times.
-                            if callsite.callee != callee_def_id {
+                            if callsite.callee != callee {
                                 callsites.push_back(CallSite {
-                                    callee: callee_def_id,
+                                    callee,
                                     substs,
                                     bb,
                                     location: terminator.source_info
@@ -190,10 +268,28 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         }
     }
 
+    /// Resolves a call of `def_id` with `substs` to the body it runs, if that
+    /// is the body of a fn item. Virtual calls through trait objects, shims
+    /// and trait methods that can't be resolved yet are not inlined, since
+    /// inlining the MIR of `def_id` there would run the wrong code.
+    fn resolve_callee(&self,
+                      param_env: ty::ParamEnv<'tcx>,
+                      def_id: DefId,
+                      substs: &'tcx Substs<'tcx>)
+                      -> Option<(DefId, &'tcx Substs<'tcx>)>
+    {
+        match Instance::resolve(self.tcx, param_env, def_id, substs) {
+            Some(Instance { def: ty::InstanceDef::Item(def_id), substs }) => Some((def_id, substs)),
+            _ => None,
+        }
+    }
+
+    /// Decides whether to inline `callsite`, returning the reason for doing
+    /// so or for not doing so.
     fn should_inline(&self,
                      callsite: CallSite<'tcx>,
                      callee_mir: &Mir<'tcx>)
-                     -> bool
+                     -> Result<String, String>
     {
         debug!("should_inline({:?})", callsite);
         let tcx = self.tcx;
@@ -201,21 +297,18 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         // Don't inline closures that have captures
         // FIXME: Handle closures better
         if callee_mir.upvar_decls.len() > 0 {
-            debug!("    upvar decls present - not inlining");
-            return false;
+            return Err("closures with captures are not inlined".to_string());
         }
 
         // Cannot inline generators which haven't been transformed yet
         if callee_mir.yield_ty.is_some() {
-            debug!("    yield ty present - not inlining");
-            return false;
+            return Err("generators are not inlined".to_string());
         }
 
         // Do not inline {u,i}128 lang items, trans const eval depends
         // on detecting calls to these lang items and intercepting them
         if tcx.is_binop_lang_item(callsite.callee).is_some() {
-            debug!("    not inlining 128bit integer lang item");
-            return false;
+            return Err("128-bit integer lang items are not inlined".to_string());
         }
 
         let trans_fn_attrs = tcx.trans_fn_attrs(callsite.callee);
@@ -225,21 +318,19 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
             // there are cases that prevent inlining that we
             // need to check for first.
             attr::InlineAttr::Always => true,
-            attr::InlineAttr::Never => {
-                debug!("#[inline(never)] present - not inlining");
-                return false
-            }
+            attr::InlineAttr::Never => return Err("#[inline(never)] present".to_string()),
             attr::InlineAttr::Hint => true,
             attr::InlineAttr::None => false,
         };
 
-        // Only inline local functions if they would be eligible for cross-crate
-        // inlining. This is to ensure that the final crate doesn't have MIR that
-        // reference unexported symbols
+        // Only inline local functions into MIR that may be inlined into other
+        // crates if they would be eligible for cross-crate inlining. This is to
+        // ensure that the final crate doesn't have MIR that reference
+        // unexported symbols
         if callsite.callee.is_local() {
-            if callsite.substs.types().count() == 0 && !hinted {
-                debug!("    callee is an exported function - not inlining");
-                return false;
+            if callsite.substs.types().count() == 0 && !hinted &&
+               tcx.is_mir_exported(self.source.def_id) {
+                return Err("the caller's MIR is exported and the callee is not".to_string());
             }
         }
 
@@ -278,13 +369,7 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
             let blk = &callee_mir.basic_blocks()[bb];
 
             for stmt in &blk.statements {
-                // Don't count StorageLive/StorageDead in the inlining cost.
-                match stmt.kind {
-                    StatementKind::StorageLive(_) |
-                    StatementKind::StorageDead(_) |
-                    StatementKind::Nop => {}
-                    _ => cost += INSTR_COST
-                }
+                cost += statement_cost(&stmt.kind);
             }
             let term = blk.terminator();
             let mut is_drop = false;
@@ -302,8 +387,6 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                         if let Some(unwind) = unwind {
                             work_list.push(unwind);
                         }
-                    } else {
-                        cost += INSTR_COST;
                     }
                 }
 
@@ -325,8 +408,21 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                         }
                     }
                 }
+                TerminatorKind::Call { .. } |
                 TerminatorKind::Assert { .. } => cost += CALL_PENALTY,
-                _ => cost += INSTR_COST
+                TerminatorKind::SwitchInt { ref values, .. } => {
+                    cost += INSTR_COST * cmp::max(values.len(), 1);
+                }
+                // These become plain jumps or disappear once inlined.
+                TerminatorKind::Goto { .. } |
+                TerminatorKind::Return |
+                TerminatorKind::Resume |
+                TerminatorKind::Abort |
+                TerminatorKind::Unreachable |
+                TerminatorKind::FalseEdges { .. } |
+                TerminatorKind::FalseUnwind { .. } => {}
+                TerminatorKind::Yield { .. } |
+                TerminatorKind::GeneratorDrop => cost += CALL_PENALTY,
             }
 
             if !is_drop {
@@ -356,15 +452,38 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         }
 
         if let attr::InlineAttr::Always = trans_fn_attrs.inline {
-            debug!("INLINING {:?} because inline(always) [cost={}]", callsite, cost);
-            true
+            Ok(format!("#[inline(always)] present, cost {}", cost))
+        } else if cost <= threshold {
+            Ok(format!("cost {} <= threshold {}", cost, threshold))
         } else {
-            if cost <= threshold {
-                debug!("INLINING {:?} [cost={} <= threshold={}]", callsite, cost, threshold);
-                true
-            } else {
-                debug!("NOT inlining {:?} [cost={} > threshold={}]", callsite, cost, threshold);
-                false
+            Err(format!("cost {} > threshold {}", cost, threshold))
+        }
+    }
+
+    /// Why the MIR of `callee` is not available.
+    fn mir_unavailable_reason(&self, callee: DefId) -> String {
+        if callee.is_local() {
+            "the callee has no MIR".to_string()
+        } else {
+            format!("the metadata of crate `{}` contains no MIR for the callee",
+                    self.tcx.crate_name(callee.krate))
+        }
+    }
+
+    /// Records the decision about `callsite` for `-Z print-mir-inlining`.
+    fn explain(&self, callsite: CallSite<'tcx>, decision: Result<&str, &str>) {
+        debug!("inlining decision for {:?}: {:?}", callsite, decision);
+        if !self.tcx.sess.opts.debugging_opts.print_mir_inlining {
+            return;
+        }
+        let caller = self.tcx.item_path_str(self.source.def_id);
+        let callee = Instance::new(callsite.callee, callsite.substs);
+        match decision {
+            Ok(reason) => {
+                println!("mir-inlining: in `{}`: inlined `{}` ({})", caller, callee, reason);
+            }
+            Err(reason) => {
+                println!("mir-inlining: in `{}`: not inlined `{}`: {}", caller, callee, reason);
             }
         }
     }
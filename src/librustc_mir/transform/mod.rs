@@ -41,11 +41,14 @@ pub mod dump_mir;
 pub mod deaggregator;
 pub mod instcombine;
 pub mod copy_prop;
+pub mod dead_store;
 pub mod const_prop;
 pub mod generator;
 pub mod inline;
 pub mod lower_128bit;
 pub mod uniform_array_move_out;
+pub mod sroa;
+pub mod gvn;
 
 pub(crate) fn provide(providers: &mut Providers) {
     self::qualify_consts::provide(providers);
@@ -267,9 +270,12 @@ fn optimized_mir<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> &'tcx
 
         instcombine::InstCombine,
         const_prop::ConstProp,
-        simplify_branches::SimplifyBranches::new("after-const-prop"),
+        simplify_branches::SimplifyBranches::with_jump_threading("after-const-prop"),
         deaggregator::Deaggregator,
+        sroa::ScalarReplacementOfAggregates,
+        gvn::GlobalValueNumbering,
         copy_prop::CopyPropagation,
+        dead_store::DeadStoreElimination,
         remove_noop_landing_pads::RemoveNoopLandingPads,
         simplify::SimplifyCfg::new("final"),
         simplify::SimplifyLocals,
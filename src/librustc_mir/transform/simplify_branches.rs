@@ -9,6 +9,18 @@
 // except according to those terms.
 
 //! A pass that simplifies branches when their condition is known.
+//!
+//! With jump threading enabled, this also folds a `SwitchInt` on a local that
+//! its own block sets to a known value, typically the discriminant of an enum
+//! that was just constructed:
+//!
+//!     _2 = std::option::Option<u32>::Some(move _3,);
+//!     _4 = discriminant(_2);
+//!     switchInt(move _4) -> [0isize: bb2, 1isize: bb3, otherwise: bb4];
+//!
+//! When the MIR optimization level is > 1, a small switch block whose value
+//! is only known in some of its predecessors is duplicated for each of them,
+//! with the copy jumping straight to the known target.
 
 use rustc::ty::{self, TyCtxt};
 use rustc::middle::const_val::ConstVal;
@@ -18,21 +30,31 @@ use transform::{MirPass, MirSource};
 
 use std::borrow::Cow;
 
-pub struct SimplifyBranches { label: String }
+pub struct SimplifyBranches { label: String, thread_jumps: bool }
 
 impl SimplifyBranches {
     pub fn new(label: &str) -> Self {
-        SimplifyBranches { label: format!("SimplifyBranches-{}", label) }
+        SimplifyBranches { label: format!("SimplifyBranches-{}", label), thread_jumps: false }
+    }
+
+    /// Also folds switches on values known from the statements before them,
+    /// and threads jumps through small switch blocks.
+    pub fn with_jump_threading(label: &str) -> Self {
+        SimplifyBranches { label: format!("SimplifyBranches-{}", label), thread_jumps: true }
     }
 }
 
+/// The largest number of statements of a block that is duplicated to thread
+/// a jump through it.
+const MAX_THREADED_STATEMENTS: usize = 8;
+
 impl MirPass for SimplifyBranches {
     fn name<'a>(&'a self) -> Cow<'a, str> {
         Cow::Borrowed(&self.label)
     }
 
     fn run_pass<'a, 'tcx>(&self,
-                          _tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
                           _src: MirSource,
                           mir: &mut Mir<'tcx>) {
         for block in mir.basic_blocks_mut() {
@@ -74,5 +96,202 @@ impl MirPass for SimplifyBranches {
                 _ => continue
             };
         }
+
+        if self.thread_jumps {
+            fold_known_switches(tcx, mir);
+            if tcx.sess.opts.debugging_opts.mir_opt_level > 1 {
+                thread_jumps(tcx, mir);
+            }
+        }
+    }
+}
+
+/// What the value switched on is, in terms of the state before the
+/// statements scanned so far.
+#[derive(Clone, Debug)]
+enum Known<'tcx> {
+    Value(u128),
+    Unknown,
+    Local(Local),
+    Discriminant(Place<'tcx>),
+}
+
+/// The local switched on by `block`, if any.
+fn switch_local(block: &BasicBlockData) -> Option<Local> {
+    match block.terminator().kind {
+        TerminatorKind::SwitchInt { discr: Operand::Copy(Place::Local(local)), .. } |
+        TerminatorKind::SwitchInt { discr: Operand::Move(Place::Local(local)), .. } => {
+            Some(local)
+        }
+        _ => None,
+    }
+}
+
+/// The target `block`'s `SwitchInt` jumps to for `value`.
+fn switch_target(block: &BasicBlockData, value: u128) -> BasicBlock {
+    match block.terminator().kind {
+        TerminatorKind::SwitchInt { ref values, ref targets, .. } => {
+            let (otherwise, targets) = targets.split_last().unwrap();
+            values.iter().zip(targets.iter()).find(|&(&v, _)| v == value)
+                  .map_or(*otherwise, |(_, &target)| target)
+        }
+        _ => bug!("switch_target: not a switch: {:?}", block.terminator()),
+    }
+}
+
+fn base_local<'a, 'tcx>(mut place: &'a Place<'tcx>) -> Option<Local> {
+    loop {
+        match *place {
+            Place::Local(local) => return Some(local),
+            Place::Static(_) => return None,
+            Place::Projection(ref proj) => {
+                if let ProjectionElem::Deref = proj.elem {
+                    return None
+                }
+                place = &proj.base;
+            }
+        }
+    }
+}
+
+/// Whether `statement` may change the value of `local` or of a part of it.
+/// Writes through references are assumed to.
+fn may_write(statement: &Statement, local: Local) -> bool {
+    match statement.kind {
+        StatementKind::Assign(ref place, _) |
+        StatementKind::SetDiscriminant { ref place, .. } => {
+            base_local(place).map_or(true, |base| base == local)
+        }
+        StatementKind::StorageLive(l) |
+        StatementKind::StorageDead(l) => l == local,
+        StatementKind::InlineAsm { .. } => true,
+        StatementKind::Validate(..) |
+        StatementKind::EndRegion(_) |
+        StatementKind::UserAssertTy(..) |
+        StatementKind::Nop => false,
+    }
+}
+
+/// Resolves `known` backwards through `statements`, as far as they tell.
+fn trace<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                   local_decls: &LocalDecls<'tcx>,
+                   statements: &[Statement<'tcx>],
+                   mut known: Known<'tcx>)
+                   -> Known<'tcx> {
+    for statement in statements.iter().rev() {
+        known = match known {
+            Known::Local(local) => match statement.kind {
+                StatementKind::Assign(Place::Local(l), ref rvalue) if l == local => {
+                    match *rvalue {
+                        Rvalue::Use(Operand::Constant(box Constant {
+                            literal: Literal::Value { ref value }, ..
+                        })) => value.val.to_raw_bits().map_or(Known::Unknown, Known::Value),
+                        Rvalue::Discriminant(ref place) if base_local(place).is_some() => {
+                            Known::Discriminant(place.clone())
+                        }
+                        _ => Known::Unknown,
+                    }
+                }
+                _ if may_write(statement, local) => Known::Unknown,
+                _ => known,
+            },
+            Known::Discriminant(place) => {
+                let variant_index = match statement.kind {
+                    StatementKind::SetDiscriminant { place: ref p, variant_index }
+                        if *p == place => Some(variant_index),
+                    StatementKind::Assign(ref p, Rvalue::Aggregate(box AggregateKind::Adt(
+                        _, variant_index, ..
+                    ), _)) if *p == place => Some(variant_index),
+                    _ => None,
+                };
+                match variant_index {
+                    Some(variant_index) => {
+                        match place.ty(local_decls, tcx).to_ty(tcx).sty {
+                            ty::TyAdt(adt_def, _) if adt_def.is_enum() => {
+                                let discr = adt_def.discriminant_for_variant(tcx, variant_index);
+                                Known::Value(discr.val)
+                            }
+                            _ => Known::Unknown,
+                        }
+                    }
+                    None if may_write(statement, base_local(&place).unwrap()) => Known::Unknown,
+                    None => Known::Discriminant(place),
+                }
+            }
+            Known::Value(_) | Known::Unknown => return known,
+        };
+    }
+    known
+}
+
+/// Replaces switches on values set earlier in the same block with gotos.
+fn fold_known_switches<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, mir: &mut Mir<'tcx>) {
+    let local_decls = mir.local_decls.clone();
+    for block in mir.basic_blocks_mut() {
+        let local = match switch_local(block) {
+            Some(local) => local,
+            None => continue,
+        };
+        if let Known::Value(value) = trace(tcx, &local_decls, &block.statements,
+                                           Known::Local(local)) {
+            let target = switch_target(block, value);
+            debug!("folding switch on {:?} = {} to {:?}", local, value, target);
+            block.terminator_mut().kind = TerminatorKind::Goto { target };
+        }
+    }
+}
+
+/// Redirects the predecessors that determine the value a small block switches
+/// on to a copy of that block which jumps to the known target.
+fn thread_jumps<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, mir: &mut Mir<'tcx>) {
+    let mut threads = vec![];
+    {
+        let predecessors = mir.predecessors();
+        for (bb, block) in mir.basic_blocks().iter_enumerated() {
+            let local = match switch_local(block) {
+                Some(local) => local,
+                None => continue,
+            };
+            let statements = block.statements.iter().filter(|statement| {
+                match statement.kind {
+                    StatementKind::Nop => false,
+                    _ => true,
+                }
+            }).count();
+            if statements > MAX_THREADED_STATEMENTS {
+                continue
+            }
+            let on_entry = match trace(tcx, &mir.local_decls, &block.statements,
+                                       Known::Local(local)) {
+                known @ Known::Local(_) | known @ Known::Discriminant(_) => known,
+                Known::Value(_) | Known::Unknown => continue,
+            };
+
+            let mut preds = predecessors[bb].clone();
+            preds.sort();
+            preds.dedup();
+            for pred in preds {
+                let pred_data = &mir[pred];
+                if pred == bb || pred_data.is_cleanup != block.is_cleanup {
+                    continue
+                }
+                match pred_data.terminator().kind {
+                    TerminatorKind::Goto { .. } => {}
+                    _ => continue,
+                }
+                if let Known::Value(value) = trace(tcx, &mir.local_decls, &pred_data.statements,
+                                                   on_entry.clone()) {
+                    threads.push((pred, bb, switch_target(block, value)));
+                }
+            }
+        }
+    }
+
+    for (pred, bb, target) in threads {
+        debug!("threading {:?} -> {:?} -> {:?}", pred, bb, target);
+        let mut copy = mir[bb].clone();
+        copy.terminator_mut().kind = TerminatorKind::Goto { target };
+        let copy = mir.basic_blocks_mut().push(copy);
+        mir[pred].terminator_mut().kind = TerminatorKind::Goto { target: copy };
     }
 }
@@ -0,0 +1,201 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Scalar replacement of aggregates.
+//!
+//! After the `Deaggregator` has turned aggregate construction into one
+//! assignment per field, a local of tuple or struct type is often only ever
+//! accessed field by field. Such a local is replaced by one new local per
+//! field:
+//!
+//!     (_2.0: u32) = const 1u32;
+//!     (_2.1: bool) = const true;
+//!     _0 = (_2.0: u32);
+//!
+//! becomes
+//!
+//!     _3 = const 1u32;
+//!     _4 = const true;
+//!     _0 = _3;
+//!
+//! which `CopyPropagation` and the passes after it can handle like any other
+//! scalar. A local is only split if it is never used as a whole: it must not
+//! be moved, copied, borrowed, dropped or assigned to in one piece. Its
+//! storage markers are removed, leaving the new locals live for the whole
+//! body.
+//!
+//! User variables are only split when the MIR optimization level is > 1, as
+//! doing so removes them from the debug info.
+
+use rustc::hir;
+use rustc::mir::*;
+use rustc::mir::visit::{MutVisitor, PlaceContext, Visitor};
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc::util::nodemap::FxHashMap;
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use transform::{MirPass, MirSource};
+
+pub struct ScalarReplacementOfAggregates;
+
+impl MirPass for ScalarReplacementOfAggregates {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        // Don't run on constant MIR, because trans might not be able to
+        // evaluate the modified MIR.
+        // FIXME(eddyb) Remove check after miri is merged.
+        let id = tcx.hir.as_local_node_id(source.def_id).unwrap();
+        match (tcx.hir.body_owner_kind(id), source.promoted) {
+            (_, Some(_)) |
+            (hir::BodyOwnerKind::Const, _) |
+            (hir::BodyOwnerKind::Static(_), _) => return,
+
+            (hir::BodyOwnerKind::Fn, _) => {
+                if tcx.is_const_fn(source.def_id) {
+                    // Don't run on const functions, as, again, trans might not be able to evaluate
+                    // the optimized IR.
+                    return
+                }
+            }
+        }
+
+        let split_user_variables = tcx.sess.opts.debugging_opts.mir_opt_level > 1;
+        let mut candidates = IdxSetBuf::new_empty(mir.local_decls.len());
+        for local in mir.local_decls.indices() {
+            let splittable = match mir.local_kind(local) {
+                LocalKind::Temp => true,
+                LocalKind::Var => split_user_variables,
+                LocalKind::Arg | LocalKind::ReturnPointer => false,
+            };
+            if splittable && is_aggregate(mir.local_decls[local].ty) {
+                candidates.add(&local);
+            }
+        }
+
+        let mut visitor = FieldUseVisitor {
+            candidates,
+            fields: FxHashMap(),
+        };
+        visitor.visit_mir(mir);
+        let FieldUseVisitor { candidates, fields } = visitor;
+
+        let mut fields: Vec<_> = fields.into_iter().filter(|&((local, _), _)| {
+            candidates.contains(&local)
+        }).collect();
+        if fields.is_empty() {
+            return
+        }
+        fields.sort_by_key(|&((local, field), _)| (local, field));
+
+        let mut replacements = FxHashMap();
+        for ((local, field), ty) in fields {
+            let span = mir.local_decls[local].source_info.span;
+            let new_local = mir.local_decls.push(LocalDecl::new_temp(ty, span));
+            debug!("replacing {:?}.{:?} with {:?}", local, field, new_local);
+            replacements.insert((local, field), new_local);
+        }
+
+        FieldReplacer {
+            candidates: &candidates,
+            replacements,
+        }.visit_mir(mir);
+    }
+}
+
+fn is_aggregate(ty: Ty) -> bool {
+    match ty.sty {
+        ty::TyTuple(tys) => !tys.is_empty(),
+        ty::TyAdt(adt_def, _) => {
+            adt_def.is_struct() && !adt_def.is_box() && !adt_def.repr.simd()
+        }
+        _ => false,
+    }
+}
+
+/// Collects the fields of the candidates that are used, and removes the
+/// candidates that are used as a whole.
+struct FieldUseVisitor<'tcx> {
+    candidates: IdxSetBuf<Local>,
+    fields: FxHashMap<(Local, Field), Ty<'tcx>>,
+}
+
+impl<'tcx> Visitor<'tcx> for FieldUseVisitor<'tcx> {
+    fn visit_place(&mut self,
+                   place: &Place<'tcx>,
+                   context: PlaceContext<'tcx>,
+                   location: Location) {
+        if let Place::Projection(ref proj) = *place {
+            if let (&Place::Local(local), &ProjectionElem::Field(field, ty)) =
+                (&proj.base, &proj.elem)
+            {
+                self.fields.insert((local, field), ty);
+                return
+            }
+        }
+
+        self.super_place(place, context, location)
+    }
+
+    fn visit_local(&mut self,
+                   &local: &Local,
+                   context: PlaceContext<'tcx>,
+                   _location: Location) {
+        match context {
+            PlaceContext::StorageLive | PlaceContext::StorageDead => {}
+            _ => {
+                self.candidates.remove(&local);
+            }
+        }
+    }
+}
+
+struct FieldReplacer<'a> {
+    candidates: &'a IdxSetBuf<Local>,
+    replacements: FxHashMap<(Local, Field), Local>,
+}
+
+impl<'a, 'tcx> MutVisitor<'tcx> for FieldReplacer<'a> {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &mut Statement<'tcx>,
+                       location: Location) {
+        let marks_split_local = match statement.kind {
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) => self.candidates.contains(&local),
+            _ => false,
+        };
+        if marks_split_local {
+            statement.make_nop();
+        } else {
+            self.super_statement(block, statement, location);
+        }
+    }
+
+    fn visit_place(&mut self,
+                   place: &mut Place<'tcx>,
+                   context: PlaceContext<'tcx>,
+                   location: Location) {
+        let replacement = match *place {
+            Place::Projection(ref proj) => match (&proj.base, &proj.elem) {
+                (&Place::Local(local), &ProjectionElem::Field(field, _)) => {
+                    self.replacements.get(&(local, field)).cloned()
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match replacement {
+            Some(new_local) => *place = Place::Local(new_local),
+            None => self.super_place(place, context, location),
+        }
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A cache of the object files and bitcode LLVM produces for codegen units
+//! (`-Z codegen-cache`), which can be shared between builds and machines.
+//!
+//! Every codegen unit is looked up under a key that hashes everything its
+//! object file depends on: the compiler version, the target, the options
+//! that affect the output, the SVH of the crate (which covers its source and
+//! the SVHs of all its dependencies) and the items of the codegen unit along
+//! with their linkage and visibility. If the cache has an entry for the key,
+//! its files are used instead of translating the codegen unit and running
+//! LLVM on it. Otherwise the files LLVM produces are stored once translation
+//! is done.
+//!
+//! Since the SVH includes spans, builds only share entries if the crate
+//! sources live in the same place, which can be arranged for with
+//! `--remap-path-prefix`. LLVM sees all codegen units at once with LTO, so
+//! the cache is not used then, nor when LLVM IR or assembly is emitted.
+//!
+//! ## Backends
+//!
+//! An entry consists of a `manifest` listing the kinds of files it holds,
+//! one per line (`object`, `bytecode` or `bytecode-compressed`), and a file
+//! named after each kind. The manifest is stored last, so that an entry
+//! without one is incomplete and treated as missing. A backend only needs to
+//! load and store these files, see `ArtifactCache`:
+//!
+//! - `-Z codegen-cache=<dir>` keeps the files in `<dir>/<key>/<file>`.
+//!   Files are written under a temporary name and renamed, so builds can
+//!   share the directory concurrently.
+//!
+//! - `-Z codegen-cache=http://<host>:<port>/<path>` talks plain HTTP/1.0 to
+//!   a server, for example `src/etc/codegen-cache-server.py`. A file is
+//!   loaded with `GET /<path>/<key>/<file>`, to which the server answers
+//!   with `200` and the contents, or `404` if it has no such file. A file is
+//!   stored with `PUT /<path>/<key>/<file>` and a `Content-Length` header, to
+//!   which the server answers with any `2xx` status once it has stored the
+//!   file.
+//!
+//! Errors of the cache are reported as warnings and otherwise ignored, the
+//! codegen unit is simply translated then.
+
+use back::write::{work_product_temp_path, CompiledModules};
+use rustc::dep_graph::WorkProductFileKind;
+use rustc::hir::def_id::LOCAL_CRATE;
+use rustc::ich::Fingerprint;
+use rustc::mir::mono::CodegenUnit;
+use rustc::session::Session;
+use rustc::session::config::{self, OutputFilenames, OutputType};
+use rustc::ty::TyCtxt;
+use rustc::util::nodemap::FxHashMap;
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+
+use std::cell::{Cell, RefCell};
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+const MANIFEST: &str = "manifest";
+
+/// How long to wait for an HTTP server before giving up on it.
+const HTTP_TIMEOUT_SECS: u64 = 30;
+
+/// Where the files of cache entries are kept. `name` is `<key>/<file>`.
+pub trait ArtifactCache {
+    /// Returns the contents of the file `name`, or `None` if there is no such
+    /// file.
+    fn load(&self, name: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Stores `data` as the file `name`, replacing the file if it exists.
+    fn store(&self, name: &str, data: &[u8]) -> io::Result<()>;
+}
+
+/// Keeps cache entries in a local directory.
+pub struct DirectoryCache {
+    root: PathBuf,
+}
+
+impl ArtifactCache for DirectoryCache {
+    fn load(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let mut data = vec![];
+        match File::open(self.root.join(name)) {
+            Ok(mut file) => file.read_to_end(&mut data)?,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Ok(Some(data))
+    }
+
+    fn store(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.root.join(name);
+        fs::create_dir_all(path.parent().unwrap())?;
+        let tmp_path = path.with_extension(format!("{}.tmp", process::id()));
+        File::create(&tmp_path)?.write_all(data)?;
+        fs::rename(&tmp_path, &path)
+    }
+}
+
+/// Keeps cache entries on an HTTP server, see the module docs for the
+/// protocol.
+pub struct HttpCache {
+    /// `<host>:<port>`
+    authority: String,
+    /// The path all files are stored under, without a trailing slash
+    prefix: String,
+}
+
+impl HttpCache {
+    fn parse(url: &str) -> Option<HttpCache> {
+        if !url.starts_with("http://") {
+            return None;
+        }
+        let rest = &url["http://".len()..];
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        if authority.is_empty() {
+            return None;
+        }
+        let authority = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:80", authority)
+        };
+        Some(HttpCache {
+            authority,
+            prefix: path.trim_right_matches('/').to_string(),
+        })
+    }
+
+    /// Sends a request and returns the status and body of the response.
+    fn request(&self, method: &str, name: &str, body: &[u8]) -> io::Result<(u32, Vec<u8>)> {
+        let mut stream = TcpStream::connect(&self.authority[..])?;
+        stream.set_read_timeout(Some(Duration::from_secs(HTTP_TIMEOUT_SECS)))?;
+        stream.set_write_timeout(Some(Duration::from_secs(HTTP_TIMEOUT_SECS)))?;
+        write!(stream,
+               "{} {}/{} HTTP/1.0\r\nHost: {}\r\nContent-Length: {}\r\n\r\n",
+               method, self.prefix, name, self.authority, body.len())?;
+        stream.write_all(body)?;
+        stream.flush()?;
+
+        // HTTP/1.0 servers close the connection after the response.
+        let mut response = vec![];
+        stream.read_to_end(&mut response)?;
+        let header_end = response.windows(4).position(|w| w == b"\r\n\r\n");
+        let header_end = match header_end {
+            Some(i) => i,
+            None => return Err(invalid_response(&self.authority)),
+        };
+        let status = String::from_utf8_lossy(&response[..header_end])
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok());
+        match status {
+            Some(status) => Ok((status, response[header_end + 4..].to_vec())),
+            None => Err(invalid_response(&self.authority)),
+        }
+    }
+}
+
+fn invalid_response(authority: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData,
+                   format!("invalid HTTP response from {}", authority))
+}
+
+impl ArtifactCache for HttpCache {
+    fn load(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        match self.request("GET", name, &[])? {
+            (200, body) => Ok(Some(body)),
+            (404, _) => Ok(None),
+            (status, _) => Err(io::Error::new(io::ErrorKind::Other,
+                                              format!("GET {} failed with status {}",
+                                                      name, status))),
+        }
+    }
+
+    fn store(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        match self.request("PUT", name, data)? {
+            (200 ... 299, _) => Ok(()),
+            (status, _) => Err(io::Error::new(io::ErrorKind::Other,
+                                              format!("PUT {} failed with status {}",
+                                                      name, status))),
+        }
+    }
+}
+
+/// The codegen cache of a session, along with the codegen units that missed
+/// it and are to be stored once LLVM is done with them.
+pub struct CodegenCache {
+    backend: Box<ArtifactCache>,
+    /// The key of every codegen unit that missed the cache, by name
+    missed: RefCell<FxHashMap<String, String>>,
+    fetched: Cell<usize>,
+}
+
+/// Opens the cache `-Z codegen-cache` points to, if any and if it can be used
+/// for this session.
+pub fn open(sess: &Session) -> Option<CodegenCache> {
+    let location = match sess.opts.debugging_opts.codegen_cache {
+        Some(ref location) => location,
+        None => return None,
+    };
+
+    let unsupported = if sess.lto() != config::Lto::No {
+        Some("LTO")
+    } else if sess.opts.cg.save_temps {
+        Some("`-C save-temps`")
+    } else if sess.opts.output_types.contains_key(&OutputType::LlvmAssembly) ||
+              sess.opts.output_types.contains_key(&OutputType::Assembly) {
+        Some("`--emit llvm-ir` or `--emit asm`")
+    } else {
+        None
+    };
+    if let Some(unsupported) = unsupported {
+        sess.warn(&format!("`-Z codegen-cache` is ignored with {}", unsupported));
+        return None;
+    }
+
+    let backend: Box<ArtifactCache> = if location.contains("://") {
+        match HttpCache::parse(location) {
+            Some(cache) => Box::new(cache),
+            None => {
+                sess.err(&format!("unsupported codegen cache URL `{}`, \
+                                   expected `http://<host>:<port>/<path>`",
+                                  location));
+                return None;
+            }
+        }
+    } else {
+        Box::new(DirectoryCache { root: PathBuf::from(location) })
+    };
+
+    Some(CodegenCache {
+        backend,
+        missed: RefCell::new(FxHashMap()),
+        fetched: Cell::new(0),
+    })
+}
+
+/// Computes the key the files of `cgu` are cached under.
+pub fn cgu_key<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, cgu: &CodegenUnit<'tcx>) -> String {
+    let mut hcx = tcx.create_stable_hashing_context();
+    let mut hasher = StableHasher::new();
+    option_env!("CFG_VERSION").hash(&mut hasher);
+    tcx.sess.opts.target_triple.hash(&mut hasher);
+    tcx.sess.target.target.data_layout.hash(&mut hasher);
+    tcx.sess.opts.dep_tracking_hash().hash(&mut hasher);
+    tcx.crate_hash(LOCAL_CRATE).as_u64().hash(&mut hasher);
+    cgu.hash_stable(&mut hcx, &mut hasher);
+    let fingerprint: Fingerprint = hasher.finish();
+    fingerprint.to_hex()
+}
+
+fn kind_name(kind: WorkProductFileKind) -> &'static str {
+    match kind {
+        WorkProductFileKind::Object => "object",
+        WorkProductFileKind::Bytecode => "bytecode",
+        WorkProductFileKind::BytecodeCompressed => "bytecode-compressed",
+    }
+}
+
+fn kind_from_name(name: &str) -> Option<WorkProductFileKind> {
+    match name {
+        "object" => Some(WorkProductFileKind::Object),
+        "bytecode" => Some(WorkProductFileKind::Bytecode),
+        "bytecode-compressed" => Some(WorkProductFileKind::BytecodeCompressed),
+        _ => None,
+    }
+}
+
+impl CodegenCache {
+    /// Copies the files cached for the codegen unit `cgu_name` to where LLVM
+    /// would have written them and returns their kinds. If the cache has no
+    /// entry for `key`, the codegen unit is remembered to be stored later.
+    pub fn fetch(&self, sess: &Session, outputs: &OutputFilenames, cgu_name: &str, key: &str)
+                 -> Option<Vec<WorkProductFileKind>> {
+        match self.try_fetch(outputs, cgu_name, key) {
+            Ok(Some(kinds)) => {
+                self.fetched.set(self.fetched.get() + 1);
+                return Some(kinds);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                sess.warn(&format!("could not fetch codegen unit `{}` from the codegen cache: {}",
+                                   cgu_name, e));
+            }
+        }
+        self.missed.borrow_mut().insert(cgu_name.to_string(), key.to_string());
+        None
+    }
+
+    fn try_fetch(&self, outputs: &OutputFilenames, cgu_name: &str, key: &str)
+                 -> io::Result<Option<Vec<WorkProductFileKind>>> {
+        let manifest = match self.backend.load(&format!("{}/{}", key, MANIFEST))? {
+            Some(manifest) => manifest,
+            None => return Ok(None),
+        };
+        let mut kinds = vec![];
+        for line in String::from_utf8_lossy(&manifest).lines() {
+            let kind = match kind_from_name(line) {
+                Some(kind) => kind,
+                None => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                                  format!("invalid manifest of entry {}", key))),
+            };
+            let data = match self.backend.load(&format!("{}/{}", key, line))? {
+                Some(data) => data,
+                // Someone must have removed part of the entry.
+                None => return Ok(None),
+            };
+            let path = work_product_temp_path(outputs, kind, cgu_name);
+            File::create(&path)?.write_all(&data)?;
+            kinds.push(kind);
+        }
+        Ok(Some(kinds))
+    }
+
+    /// Stores the files of the codegen units that missed the cache.
+    pub(crate) fn store(&self, sess: &Session, compiled_modules: &CompiledModules) {
+        let missed = self.missed.borrow();
+        let mut stored = 0;
+        for module in &compiled_modules.modules {
+            let key = match missed.get(&module.name) {
+                Some(key) => key,
+                None => continue,
+            };
+            match self.store_module(key, &module.files()) {
+                Ok(()) => stored += 1,
+                Err(e) => {
+                    sess.warn(&format!("could not store codegen unit `{}` in the codegen cache: \
+                                        {}", module.name, e));
+                }
+            }
+        }
+
+        if sess.opts.debugging_opts.incremental_info {
+            println!("[codegen-cache] fetched {} and stored {} of {} codegen units",
+                     self.fetched.get(), stored, compiled_modules.modules.len());
+        }
+    }
+
+    fn store_module(&self, key: &str, files: &[(WorkProductFileKind, PathBuf)])
+                    -> io::Result<()> {
+        let mut manifest = String::new();
+        for &(kind, ref path) in files {
+            let mut data = vec![];
+            File::open(path)?.read_to_end(&mut data)?;
+            self.backend.store(&format!("{}/{}", key, kind_name(kind)), &data)?;
+            manifest.push_str(kind_name(kind));
+            manifest.push('\n');
+        }
+        self.backend.store(&format!("{}/{}", key, MANIFEST), manifest.as_bytes())
+    }
+}
+
@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use back::artifact_cache::{self, CodegenCache};
 use back::bytecode::{self, RLIB_BYTECODE_EXTENSION};
 use back::lto::{self, ModuleBuffer, ThinBuffer};
 use back::link::{self, get_linker, remove};
@@ -507,8 +508,8 @@ unsafe fn optimize(cgcx: &CodegenContext,
 {
     let (llmod, llcx, tm) = match mtrans.source {
         ModuleSource::Translated(ref llvm) => (llvm.llmod, llvm.llcx, llvm.tm),
-        ModuleSource::Preexisting(_) => {
-            bug!("optimize_and_codegen: called with ModuleSource::Preexisting")
+        ModuleSource::Preexisting(_) | ModuleSource::Cached(_) => {
+            bug!("optimize_and_codegen: called with a pre-existing module")
         }
     };
 
@@ -629,8 +630,8 @@ unsafe fn codegen(cgcx: &CodegenContext,
     timeline.record("codegen");
     let (llmod, llcx, tm) = match mtrans.source {
         ModuleSource::Translated(ref llvm) => (llvm.llmod, llvm.llcx, llvm.tm),
-        ModuleSource::Preexisting(_) => {
-            bug!("codegen: called with ModuleSource::Preexisting")
+        ModuleSource::Preexisting(_) | ModuleSource::Cached(_) => {
+            bug!("codegen: called with a pre-existing module")
         }
     };
     let module_name = mtrans.name.clone();
@@ -1026,6 +1027,23 @@ pub fn start_async_translation(tcx: TyCtxt,
         shared_emitter_main,
         future: coordinator_thread,
         output_filenames: tcx.output_filenames(LOCAL_CRATE),
+        codegen_cache: artifact_cache::open(sess),
+    }
+}
+
+/// Returns the path LLVM writes the file of the given kind for the module
+/// `name` to.
+pub(crate) fn work_product_temp_path(outputs: &OutputFilenames,
+                                     kind: WorkProductFileKind,
+                                     name: &str)
+                                     -> PathBuf {
+    match kind {
+        WorkProductFileKind::Object => outputs.temp_path(OutputType::Object, Some(name)),
+        WorkProductFileKind::Bytecode => outputs.temp_path(OutputType::Bitcode, Some(name)),
+        WorkProductFileKind::BytecodeCompressed => {
+            outputs.temp_path(OutputType::Bitcode, Some(name))
+                .with_extension(RLIB_BYTECODE_EXTENSION)
+        }
     }
 }
 
@@ -1037,19 +1055,7 @@ fn copy_module_artifacts_into_incr_comp_cache(sess: &Session,
     }
 
     for module in compiled_modules.modules.iter() {
-        let mut files = vec![];
-
-        if let Some(ref path) = module.object {
-            files.push((WorkProductFileKind::Object, path.clone()));
-        }
-        if let Some(ref path) = module.bytecode {
-            files.push((WorkProductFileKind::Bytecode, path.clone()));
-        }
-        if let Some(ref path) = module.bytecode_compressed {
-            files.push((WorkProductFileKind::BytecodeCompressed, path.clone()));
-        }
-
-        save_trans_partition(sess, dep_graph, &module.name, &files);
+        save_trans_partition(sess, dep_graph, &module.name, &module.files());
     }
 }
 
@@ -1254,38 +1260,39 @@ fn execute_work_item(cgcx: &CodegenContext,
     };
     let module_name = mtrans.name.clone();
 
-    let pre_existing = match mtrans.source {
+    // The files of pre-existing modules are copied from the incr. comp.
+    // directory, the ones of cached modules were fetched already.
+    let pre_existing: Option<Vec<(WorkProductFileKind, Option<String>)>> = match mtrans.source {
         ModuleSource::Translated(_) => None,
-        ModuleSource::Preexisting(ref wp) => Some(wp.clone()),
+        ModuleSource::Preexisting(ref wp) => {
+            Some(wp.saved_files.iter().map(|&(kind, ref file)| {
+                (kind, Some(file.clone()))
+            }).collect())
+        }
+        ModuleSource::Cached(ref kinds) => Some(kinds.iter().map(|&kind| (kind, None)).collect()),
     };
 
-    if let Some(wp) = pre_existing {
-        let incr_comp_session_dir = cgcx.incr_comp_session_dir
-                                        .as_ref()
-                                        .unwrap();
+    if let Some(files) = pre_existing {
         let name = &mtrans.name;
         let mut object = None;
         let mut bytecode = None;
         let mut bytecode_compressed = None;
-        for (kind, saved_file) in wp.saved_files {
-            let obj_out = match kind {
-                WorkProductFileKind::Object => {
-                    let path = cgcx.output_filenames.temp_path(OutputType::Object, Some(name));
-                    object = Some(path.clone());
-                    path
-                }
-                WorkProductFileKind::Bytecode => {
-                    let path = cgcx.output_filenames.temp_path(OutputType::Bitcode, Some(name));
-                    bytecode = Some(path.clone());
-                    path
-                }
+        for (kind, saved_file) in files {
+            let obj_out = work_product_temp_path(&cgcx.output_filenames, kind, name);
+            match kind {
+                WorkProductFileKind::Object => object = Some(obj_out.clone()),
+                WorkProductFileKind::Bytecode => bytecode = Some(obj_out.clone()),
                 WorkProductFileKind::BytecodeCompressed => {
-                    let path = cgcx.output_filenames.temp_path(OutputType::Bitcode, Some(name))
-                        .with_extension(RLIB_BYTECODE_EXTENSION);
-                    bytecode_compressed = Some(path.clone());
-                    path
+                    bytecode_compressed = Some(obj_out.clone())
                 }
+            }
+            let saved_file = match saved_file {
+                Some(saved_file) => saved_file,
+                None => continue,
             };
+            let incr_comp_session_dir = cgcx.incr_comp_session_dir
+                                            .as_ref()
+                                            .unwrap();
             let source_file = in_incr_comp_dir(&incr_comp_session_dir,
                                                &saved_file);
             debug!("copying pre-existing module `{}` from {:?} to {}",
@@ -2218,6 +2225,7 @@ pub struct OngoingCrateTranslation {
     shared_emitter_main: SharedEmitterMain,
     future: thread::JoinHandle<Result<CompiledModules, ()>>,
     output_filenames: Arc<OutputFilenames>,
+    codegen_cache: Option<CodegenCache>,
 }
 
 impl OngoingCrateTranslation {
@@ -2243,6 +2251,9 @@ impl OngoingCrateTranslation {
         copy_module_artifacts_into_incr_comp_cache(sess,
                                                    dep_graph,
                                                    &compiled_modules);
+        if let Some(ref codegen_cache) = self.codegen_cache {
+            codegen_cache.store(sess, &compiled_modules);
+        }
         produce_final_output_artifacts(sess,
                                        &compiled_modules,
                                        &self.output_filenames);
@@ -2280,6 +2291,10 @@ impl OngoingCrateTranslation {
         submit_translated_module_to_llvm(tcx, mtrans, cost);
     }
 
+    pub(crate) fn codegen_cache(&self) -> Option<&CodegenCache> {
+        self.codegen_cache.as_ref()
+    }
+
     pub fn translation_finished(&self, tcx: TyCtxt) {
         self.wait_for_signal_to_translate_item();
         self.check_for_errors(tcx.sess);
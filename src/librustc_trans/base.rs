@@ -29,6 +29,7 @@ use super::ModuleTranslation;
 use super::ModuleKind;
 
 use abi;
+use back::artifact_cache;
 use back::link;
 use back::write::{self, OngoingCrateTranslation, create_target_machine};
 use llvm::{ContextRef, ModuleRef, ValueRef, Vector, get_param};
@@ -45,7 +46,7 @@ use rustc::dep_graph::{DepNode, DepConstructor};
 use rustc::ty::subst::Kind;
 use rustc::middle::cstore::{self, LinkMeta, LinkagePreference};
 use rustc::middle::exported_symbols;
-use rustc::util::common::{time, print_time_passes_entry};
+use rustc::util::common::{time, print_time_passes_entry, profq_msg, ProfileQueriesMsg};
 use rustc::session::config::{self, NoDebugInfo};
 use rustc::session::Session;
 use rustc_incremental;
@@ -492,8 +493,16 @@ pub fn trans_instance<'a, 'tcx>(cx: &CodegenCx<'a, 'tcx>, instance: Instance<'tc
         attributes::emit_uwtable(lldecl, true);
     }
 
+    let insns_start = cx.stats.borrow().n_llvm_insns;
+
     let mir = cx.tcx.instance_mir(instance.def);
     mir::trans_mir(cx, lldecl, &mir, instance, sig);
+
+    if cx.sess().opts.debugging_opts.print_mono_bloat {
+        let mut stats = cx.stats.borrow_mut();
+        let insns = stats.n_llvm_insns - insns_start;
+        stats.instance_insns.push((cx.tcx.symbol_name(instance).to_string(), insns));
+    }
 }
 
 pub fn set_link_section(cx: &CodegenCx,
@@ -870,6 +879,23 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             }
         }
 
+        // Next, we try to fetch the object files of the codegen unit from
+        // the codegen cache, if there is one.
+        if let Some(codegen_cache) = ongoing_translation.codegen_cache() {
+            let key = artifact_cache::cgu_key(tcx, &cgu);
+            let outputs = tcx.output_filenames(LOCAL_CRATE);
+            if let Some(kinds) = codegen_cache.fetch(tcx.sess, &outputs, cgu.name(), &key) {
+                let module = ModuleTranslation {
+                    name: cgu.name().to_string(),
+                    source: ModuleSource::Cached(kinds),
+                    kind: ModuleKind::Regular,
+                    llmod_id: format!("{}.rs", cgu.name()),
+                };
+                write::submit_translated_module_to_llvm(tcx, module, 0);
+                continue
+            }
+        }
+
         let _timing_guard = time_graph.as_ref().map(|time_graph| {
             time_graph.start(write::TRANS_WORKER_TIMELINE,
                              write::TRANS_WORK_PACKAGE_KIND,
@@ -917,6 +943,17 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         }
     }
 
+    if tcx.sess.opts.debugging_opts.print_mono_bloat {
+        // The collection results are not kept around after partitioning, so
+        // we collect the mono items once more, reusing the cached MIR.
+        let (items, inlining_map) =
+            collector::collect_crate_mono_items(tcx, mono_item_collection_mode(tcx));
+        ::rustc_mir::monomorphize::bloat::print_report(tcx,
+                                                       &items,
+                                                       &inlining_map,
+                                                       &all_stats.instance_insns);
+    }
+
     ongoing_translation.check_for_errors(tcx.sess);
 
     assert_and_save_dep_graph(tcx);
@@ -933,14 +970,8 @@ fn assert_and_save_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
          || rustc_incremental::save_dep_graph(tcx));
 }
 
-fn collect_and_partition_translation_items<'a, 'tcx>(
-    tcx: TyCtxt<'a, 'tcx, 'tcx>,
-    cnum: CrateNum,
-) -> (Arc<DefIdSet>, Arc<Vec<Arc<CodegenUnit<'tcx>>>>)
-{
-    assert_eq!(cnum, LOCAL_CRATE);
-
-    let collection_mode = match tcx.sess.opts.debugging_opts.print_trans_items {
+fn mono_item_collection_mode(tcx: TyCtxt) -> MonoItemCollectionMode {
+    match tcx.sess.opts.debugging_opts.print_trans_items {
         Some(ref s) => {
             let mode_string = s.to_lowercase();
             let mode_string = mode_string.trim();
@@ -964,7 +995,17 @@ fn collect_and_partition_translation_items<'a, 'tcx>(
                 MonoItemCollectionMode::Lazy
             }
         }
-    };
+    }
+}
+
+fn collect_and_partition_translation_items<'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    cnum: CrateNum,
+) -> (Arc<DefIdSet>, Arc<Vec<Arc<CodegenUnit<'tcx>>>>)
+{
+    assert_eq!(cnum, LOCAL_CRATE);
+
+    let collection_mode = mono_item_collection_mode(tcx);
 
     let (items, inlining_map) =
         time(tcx.sess, "translation item collection", || {
@@ -1152,8 +1193,22 @@ fn compile_codegen_unit<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             }
 
             // ... and now that we have everything pre-defined, fill out those definitions.
+            let profile = tcx.sess.self_profiling();
             for &(trans_item, _) in &trans_items {
+                if profile {
+                    let def_id = match trans_item {
+                        MonoItem::Fn(instance) => instance.def_id(),
+                        MonoItem::Static(def_id) => def_id,
+                        MonoItem::GlobalAsm(node_id) => tcx.hir.local_def_id(node_id),
+                    };
+                    let msg = ProfileQueriesMsg::TransItemBegin(def_id,
+                                                                trans_item.to_string(tcx));
+                    profq_msg(tcx.sess, msg);
+                }
                 trans_item.define(&cx);
+                if profile {
+                    profq_msg(tcx.sess, ProfileQueriesMsg::TransItemEnd);
+                }
             }
 
             // If this codegen unit contains the main function, also create the
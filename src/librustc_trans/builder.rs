@@ -101,7 +101,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
     }
 
     fn count_insn(&self, category: &str) {
-        if self.cx.sess().trans_stats() {
+        if self.cx.sess().trans_stats() || self.cx.sess().opts.debugging_opts.print_mono_bloat {
             self.cx.stats.borrow_mut().n_llvm_insns += 1;
         }
         if self.cx.sess().count_llvm_insns() {
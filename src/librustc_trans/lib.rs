@@ -35,7 +35,7 @@
 #![feature(optin_builtin_traits)]
 #![feature(inclusive_range_fields)]
 
-use rustc::dep_graph::WorkProduct;
+use rustc::dep_graph::{WorkProduct, WorkProductFileKind};
 use syntax_pos::symbol::Symbol;
 
 #[macro_use]
@@ -90,6 +90,7 @@ mod diagnostics;
 mod back {
     pub use rustc_trans_utils::symbol_names;
     mod archive;
+    pub mod artifact_cache;
     pub mod bytecode;
     mod command;
     pub mod linker;
@@ -293,7 +294,8 @@ impl ModuleTranslation {
     fn llvm(&self) -> Option<&ModuleLlvm> {
         match self.source {
             ModuleSource::Translated(ref llvm) => Some(llvm),
-            ModuleSource::Preexisting(_) => None,
+            ModuleSource::Preexisting(_) |
+            ModuleSource::Cached(_) => None,
         }
     }
 
@@ -303,7 +305,8 @@ impl ModuleTranslation {
                                 emit_bc_compressed: bool,
                                 outputs: &OutputFilenames) -> CompiledModule {
         let pre_existing = match self.source {
-            ModuleSource::Preexisting(_) => true,
+            ModuleSource::Preexisting(_) |
+            ModuleSource::Cached(_) => true,
             ModuleSource::Translated(_) => false,
         };
         let object = if emit_obj {
@@ -346,10 +349,31 @@ struct CompiledModule {
     bytecode_compressed: Option<PathBuf>,
 }
 
+impl CompiledModule {
+    /// The files LLVM produced for this module, by kind.
+    fn files(&self) -> Vec<(WorkProductFileKind, PathBuf)> {
+        let mut files = vec![];
+        if let Some(ref path) = self.object {
+            files.push((WorkProductFileKind::Object, path.clone()));
+        }
+        if let Some(ref path) = self.bytecode {
+            files.push((WorkProductFileKind::Bytecode, path.clone()));
+        }
+        if let Some(ref path) = self.bytecode_compressed {
+            files.push((WorkProductFileKind::BytecodeCompressed, path.clone()));
+        }
+        files
+    }
+}
+
 enum ModuleSource {
     /// Copy the `.o` files or whatever from the incr. comp. directory.
     Preexisting(WorkProduct),
 
+    /// The files were fetched from the `-Z codegen-cache` into their temp
+    /// paths already.
+    Cached(Vec<WorkProductFileKind>),
+
     /// Rebuild from this LLVM module.
     Translated(ModuleLlvm),
 }
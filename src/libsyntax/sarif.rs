@@ -0,0 +1,501 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A SARIF emitter for errors.
+//!
+//! Unlike the JSON emitter, which prints every diagnostic as soon as it is
+//! emitted, this emitter collects all diagnostics of a session and writes a
+//! single [SARIF 2.1.0] log once the emitter is dropped, since a SARIF log is
+//! one JSON document describing a whole run of a tool.
+//!
+//! Every diagnostic becomes a result whose rule is its error code or lint
+//! name, if it has one. Primary spans become its locations, secondary spans
+//! and spans of notes become its related locations, and machine applicable
+//! suggestions become its fixes.
+//!
+//! Diagnostics reported before the session's emitter exists, e.g. about
+//! command line flags, are buffered with `buffer_early_diagnostic` and written
+//! with the log of the next emitter that is dropped, so that a compilation
+//! only ever writes one log.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use codemap::{CodeMap, FilePathMapping};
+use syntax_pos::{FileName, Span, SpanLabel, DUMMY_SP};
+use errors::registry::Registry;
+use errors::{DiagnosticBuilder, DiagnosticId, CodeMapper, Level};
+use errors::emitter::Emitter;
+
+use rustc_data_structures::sync::{self, Lrc};
+use std::cell::{Cell, RefCell};
+use std::io::{self, Write};
+use std::mem;
+
+use rustc_serialize::{Encodable, Encoder};
+use rustc_serialize::json::as_pretty_json;
+
+const SARIF_SCHEMA: &str =
+    "https://schemastore.azurewebsites.net/schemas/json/sarif-2.1.0-rtm.4.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+thread_local! {
+    static EARLY_DIAGNOSTICS: RefCell<Vec<(Level, String)>> = RefCell::new(vec![]);
+    static LIVE_EMITTERS: Cell<usize> = Cell::new(0);
+}
+
+/// Buffers a diagnostic without a span until the next `SarifEmitter` writes
+/// its log.
+pub fn buffer_early_diagnostic(level: Level, msg: &str) {
+    EARLY_DIAGNOSTICS.with(|diagnostics| {
+        diagnostics.borrow_mut().push((level, msg.to_owned()))
+    });
+}
+
+/// Whether a `SarifEmitter` exists that will write the buffered diagnostics.
+pub fn has_live_emitter() -> bool {
+    LIVE_EMITTERS.with(|live| live.get() > 0)
+}
+
+/// The SARIF level of a diagnostic of `level`, if it is reported at all.
+fn sarif_level(level: Level) -> Option<&'static str> {
+    match level {
+        Level::Bug | Level::Fatal | Level::PhaseFatal | Level::Error => Some("error"),
+        Level::Warning => Some("warning"),
+        Level::Note | Level::Help => Some("note"),
+        // Cancelled diagnostics are never shown, and failure notes like
+        // "For more information about this error, try `rustc --explain`"
+        // only make sense on a terminal.
+        Level::Cancelled | Level::FailureNote => None,
+    }
+}
+
+pub struct SarifEmitter {
+    dst: Box<Write + Send>,
+    registry: Option<Registry>,
+    cm: Lrc<CodeMapper + sync::Send + sync::Sync>,
+    /// The rules referenced by `results`, in order of first use.
+    rules: Vec<ReportingDescriptor>,
+    results: Vec<SarifResult>,
+}
+
+impl SarifEmitter {
+    pub fn stderr(registry: Option<Registry>, code_map: Lrc<CodeMap>) -> SarifEmitter {
+        SarifEmitter::new(Box::new(io::stderr()), registry, code_map)
+    }
+
+    pub fn basic() -> SarifEmitter {
+        let file_path_mapping = FilePathMapping::empty();
+        SarifEmitter::stderr(None, Lrc::new(CodeMap::new(file_path_mapping)))
+    }
+
+    pub fn new(dst: Box<Write + Send>,
+               registry: Option<Registry>,
+               code_map: Lrc<CodeMap>) -> SarifEmitter {
+        LIVE_EMITTERS.with(|live| live.set(live.get() + 1));
+        SarifEmitter {
+            dst,
+            registry,
+            cm: code_map,
+            rules: vec![],
+            results: vec![],
+        }
+    }
+
+    /// Returns the index of the rule for `code` in `self.rules`, adding the
+    /// rule if this is the first result referencing it.
+    fn rule_index(&mut self, code: &DiagnosticId) -> usize {
+        let (id, help_uri) = match *code {
+            DiagnosticId::Error(ref code) => {
+                let uri = format!("https://doc.rust-lang.org/error-index.html#{}", code);
+                (code, Some(uri))
+            }
+            DiagnosticId::Lint(ref name) => (name, None),
+        };
+        if let Some(index) = self.rules.iter().position(|rule| rule.id == *id) {
+            return index;
+        }
+
+        let full_description = self.registry
+                                   .as_ref()
+                                   .and_then(|registry| registry.find_description(id))
+                                   .map(|text| Message { text: text.trim().to_owned() });
+        self.rules.push(ReportingDescriptor {
+            id: id.clone(),
+            full_description,
+            help_uri,
+        });
+        self.rules.len() - 1
+    }
+
+    fn region(&self, span: Span) -> Region {
+        let start = self.cm.lookup_char_pos(span.lo());
+        let end = self.cm.lookup_char_pos(span.hi());
+        Region {
+            startLine: start.line,
+            startColumn: start.col.0 + 1,
+            endLine: end.line,
+            endColumn: end.col.0 + 1,
+            byteOffset: span.lo().0 - start.file.start_pos.0,
+            byteLength: span.hi().0 - span.lo().0,
+        }
+    }
+
+    fn artifact_location(&self, span: Span) -> ArtifactLocation {
+        let start = self.cm.lookup_char_pos(span.lo());
+        ArtifactLocation { uri: file_uri(&start.file.name) }
+    }
+
+    fn location(&self, span: Span, message: Option<String>) -> Location {
+        let span = self.cm.call_span_if_macro(span);
+        Location {
+            physical_location: PhysicalLocation {
+                artifactLocation: self.artifact_location(span),
+                region: self.region(span),
+            },
+            message: message.map(|text| Message { text }),
+        }
+    }
+
+    fn fixes(&self, db: &DiagnosticBuilder) -> Vec<Fix> {
+        // Approximate suggestions contain placeholders, so they must not be
+        // applied by tools and are left out.
+        db.suggestions.iter().filter(|sugg| !sugg.approximate).flat_map(|sugg| {
+            sugg.substitutions.iter().map(move |substitution| {
+                let mut changes: Vec<ArtifactChange> = vec![];
+                for part in &substitution.parts {
+                    let replacement = Replacement {
+                        deletedRegion: self.region(part.span),
+                        insertedContent: ArtifactContent { text: part.snippet.clone() },
+                    };
+                    let artifact = self.artifact_location(part.span);
+                    match changes.iter().position(|c| c.artifactLocation.uri == artifact.uri) {
+                        Some(i) => changes[i].replacements.push(replacement),
+                        None => changes.push(ArtifactChange {
+                            artifactLocation: artifact,
+                            replacements: vec![replacement],
+                        }),
+                    }
+                }
+                Fix {
+                    description: Message { text: sugg.msg.clone() },
+                    artifactChanges: changes,
+                }
+            })
+        }).collect()
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit(&mut self, db: &DiagnosticBuilder) {
+        let level = match sarif_level(db.level) {
+            Some(level) => level,
+            None => return,
+        };
+
+        let mut text = db.message();
+        let mut locations = vec![];
+        let mut related_locations = vec![];
+        for SpanLabel { span, is_primary, label } in db.span.span_labels() {
+            if span == DUMMY_SP {
+                continue;
+            }
+            if is_primary {
+                locations.push(self.location(span, label));
+            } else {
+                related_locations.push(self.location(span, label));
+            }
+        }
+
+        // Notes and helps with a span become related locations, the others
+        // are appended to the message like the human readable output does.
+        for child in &db.children {
+            let message = format!("{}: {}", child.level, child.message());
+            let span = child.render_span.as_ref().unwrap_or(&child.span);
+            let spans = span.primary_spans()
+                            .iter()
+                            .filter(|&&sp| sp != DUMMY_SP)
+                            .cloned()
+                            .collect::<Vec<_>>();
+            if spans.is_empty() {
+                text.push('\n');
+                text.push_str(&message);
+            }
+            for sp in spans {
+                related_locations.push(self.location(sp, Some(message.clone())));
+            }
+        }
+
+        let rule_index = db.code.as_ref().map(|code| self.rule_index(code));
+        let result = SarifResult {
+            rule_id: rule_index.map(|i| self.rules[i].id.clone()),
+            rule_index,
+            level,
+            message: Message { text },
+            locations,
+            related_locations,
+            fixes: self.fixes(db),
+        };
+        self.results.push(result);
+    }
+
+    fn should_show_explain(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        LIVE_EMITTERS.with(|live| live.set(live.get() - 1));
+        let early = EARLY_DIAGNOSTICS.with(|diagnostics| {
+            mem::replace(&mut *diagnostics.borrow_mut(), vec![])
+        });
+        let early = early.into_iter().filter_map(|(level, text)| {
+            sarif_level(level).map(|level| SarifResult {
+                rule_id: None,
+                rule_index: None,
+                level,
+                message: Message { text },
+                locations: vec![],
+                related_locations: vec![],
+                fixes: vec![],
+            })
+        }).collect::<Vec<_>>();
+        self.results.splice(0..0, early);
+
+        let execution_successful = self.results.iter().all(|result| result.level != "error");
+        let log = SarifLog {
+            runs: vec![Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: "rustc",
+                        informationUri: "https://www.rust-lang.org/",
+                        rules: mem::replace(&mut self.rules, vec![]),
+                    },
+                },
+                invocations: vec![Invocation { executionSuccessful: execution_successful }],
+                results: mem::replace(&mut self.results, vec![]),
+                columnKind: "unicodeCodePoints",
+            }],
+        };
+        // We may be dropped while unwinding from a fatal error, so failing to
+        // write the log must not panic.
+        let _ = writeln!(&mut self.dst, "{}", as_pretty_json(&log));
+    }
+}
+
+/// Turns a file name into the URI of a SARIF artifact location. Relative
+/// paths stay relative references, which SARIF resolves against the
+/// directory the compiler was invoked in.
+fn file_uri(name: &FileName) -> String {
+    let path = name.to_string().replace('\\', "/");
+    let mut uri = String::with_capacity(path.len());
+    if path.starts_with('/') {
+        uri.push_str("file://");
+    } else if path.as_bytes().get(1) == Some(&b':') {
+        // A Windows path with a drive letter.
+        uri.push_str("file:///");
+    }
+    for c in path.chars() {
+        match c {
+            ' ' => uri.push_str("%20"),
+            '#' => uri.push_str("%23"),
+            '%' => uri.push_str("%25"),
+            '?' => uri.push_str("%3F"),
+            c => uri.push(c),
+        }
+    }
+    uri
+}
+
+// The following data types are provided just for serialisation. Their field
+// names follow the SARIF specification, hence the camel case. Types with
+// optional properties are encoded by hand, since SARIF does not allow `null`
+// in place of a missing property.
+
+struct SarifLog {
+    runs: Vec<Run>,
+}
+
+impl Encodable for SarifLog {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_struct("SarifLog", 3, |s| {
+            s.emit_struct_field("$schema", 0, |s| SARIF_SCHEMA.encode(s))?;
+            s.emit_struct_field("version", 1, |s| SARIF_VERSION.encode(s))?;
+            s.emit_struct_field("runs", 2, |s| self.runs.encode(s))
+        })
+    }
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct Run {
+    tool: Tool,
+    invocations: Vec<Invocation>,
+    results: Vec<SarifResult>,
+    /// How `startColumn` and `endColumn` of regions count characters.
+    columnKind: &'static str,
+}
+
+#[derive(RustcEncodable)]
+struct Tool {
+    driver: ToolComponent,
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct ToolComponent {
+    name: &'static str,
+    informationUri: &'static str,
+    rules: Vec<ReportingDescriptor>,
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct Invocation {
+    /// Whether the compilation finished without errors.
+    executionSuccessful: bool,
+}
+
+/// A rule, i.e. an error code or a lint.
+struct ReportingDescriptor {
+    id: String,
+    /// The long explanation of an error code, as shown by `rustc --explain`.
+    full_description: Option<Message>,
+    help_uri: Option<String>,
+}
+
+impl Encodable for ReportingDescriptor {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_struct("ReportingDescriptor", 3, |s| {
+            s.emit_struct_field("id", 0, |s| self.id.encode(s))?;
+            if let Some(ref description) = self.full_description {
+                s.emit_struct_field("fullDescription", 1, |s| description.encode(s))?;
+            }
+            if let Some(ref uri) = self.help_uri {
+                s.emit_struct_field("helpUri", 2, |s| uri.encode(s))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// A single diagnostic.
+struct SarifResult {
+    rule_id: Option<String>,
+    /// Index of the rule in the `rules` of the tool.
+    rule_index: Option<usize>,
+    /// "error", "warning" or "note".
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    related_locations: Vec<Location>,
+    fixes: Vec<Fix>,
+}
+
+impl Encodable for SarifResult {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_struct("SarifResult", 7, |s| {
+            s.emit_struct_field("level", 0, |s| self.level.encode(s))?;
+            s.emit_struct_field("message", 1, |s| self.message.encode(s))?;
+            if let Some(ref rule_id) = self.rule_id {
+                s.emit_struct_field("ruleId", 2, |s| rule_id.encode(s))?;
+            }
+            if let Some(rule_index) = self.rule_index {
+                s.emit_struct_field("ruleIndex", 3, |s| rule_index.encode(s))?;
+            }
+            s.emit_struct_field("locations", 4, |s| self.locations.encode(s))?;
+            if !self.related_locations.is_empty() {
+                s.emit_struct_field("relatedLocations", 5, |s| {
+                    self.related_locations.encode(s)
+                })?;
+            }
+            if !self.fixes.is_empty() {
+                s.emit_struct_field("fixes", 6, |s| self.fixes.encode(s))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(RustcEncodable)]
+struct Message {
+    text: String,
+}
+
+struct Location {
+    physical_location: PhysicalLocation,
+    /// The label of the span.
+    message: Option<Message>,
+}
+
+impl Encodable for Location {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_struct("Location", 2, |s| {
+            s.emit_struct_field("physicalLocation", 0, |s| self.physical_location.encode(s))?;
+            if let Some(ref message) = self.message {
+                s.emit_struct_field("message", 1, |s| message.encode(s))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct PhysicalLocation {
+    artifactLocation: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(RustcEncodable)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct Region {
+    /// 1-based.
+    startLine: usize,
+    /// 1-based, character offset.
+    startColumn: usize,
+    endLine: usize,
+    /// 1-based, character offset of the first character after the region.
+    endColumn: usize,
+    /// 0-based, from the start of the file.
+    byteOffset: u32,
+    byteLength: u32,
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct Fix {
+    description: Message,
+    artifactChanges: Vec<ArtifactChange>,
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct ArtifactChange {
+    artifactLocation: ArtifactLocation,
+    replacements: Vec<Replacement>,
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct Replacement {
+    deletedRegion: Region,
+    insertedContent: ArtifactContent,
+}
+
+#[derive(RustcEncodable)]
+struct ArtifactContent {
+    text: String,
+}
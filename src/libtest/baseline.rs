@@ -0,0 +1,148 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Saved benchmark results (`--save-baseline` and `--baseline`).
+//!
+//! A baseline stores the samples of every benchmark of one test binary, so
+//! that a later run can tell with Welch's t-test whether a benchmark really
+//! got slower or faster, rather than just comparing two medians. Baselines
+//! live next to the test binary, in `baselines/<NAME>/<binary name>`, as a
+//! text file with one line per benchmark: its name, a tab, and its samples
+//! in ns/iter separated by spaces.
+
+use super::*;
+use stats::Stats;
+use std::fs;
+use std::io::BufReader;
+
+/// A change is only significant if a difference in means at least this large
+/// would occur by chance in fewer than 5% of runs.
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// Significant changes of the median smaller than this percentage are still
+/// considered noise.
+const NOISE_THRESHOLD_PCT: f64 = 2.0;
+
+/// How a benchmark compares to the same benchmark in a baseline.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct BenchComparison {
+    /// Median of the baseline, in ns/iter
+    pub baseline_median: f64,
+    /// Change of the median relative to the baseline, in percent
+    pub change_pct: f64,
+    /// Two-tailed p-value of Welch's t-test between the baseline samples and
+    /// the new ones
+    pub p_value: f64,
+}
+
+impl BenchComparison {
+    fn is_significant(&self) -> bool {
+        self.p_value < SIGNIFICANCE_LEVEL && self.change_pct.abs() > NOISE_THRESHOLD_PCT
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.is_significant() && self.change_pct > 0.0
+    }
+
+    pub fn is_improvement(&self) -> bool {
+        self.is_significant() && self.change_pct < 0.0
+    }
+
+    /// One word describing the change, as used by the formatters.
+    pub fn verdict(&self) -> &'static str {
+        if self.is_regression() {
+            "regressed"
+        } else if self.is_improvement() {
+            "improved"
+        } else {
+            "no change"
+        }
+    }
+}
+
+/// The samples of a set of benchmarks, keyed by benchmark name.
+#[derive(Default)]
+pub(crate) struct Baseline {
+    samples: BTreeMap<String, Vec<f64>>,
+}
+
+impl Baseline {
+    pub fn new() -> Baseline {
+        Baseline::default()
+    }
+
+    pub fn insert(&mut self, name: &str, samples: Vec<f64>) {
+        if !samples.is_empty() {
+            self.samples.insert(name.to_owned(), samples);
+        }
+    }
+
+    /// Compares the samples of a benchmark with the ones saved for it, if any.
+    pub fn compare(&self, name: &str, samples: &[f64]) -> Option<BenchComparison> {
+        let old = self.samples.get(name)?;
+        let ttest = stats::welch_t_test(old, samples)?;
+        let baseline_median = old.median();
+        if baseline_median <= 0.0 {
+            return None;
+        }
+        let change_pct = (samples.median() - baseline_median) / baseline_median * 100.0;
+
+        Some(BenchComparison {
+            baseline_median,
+            change_pct,
+            p_value: ttest.p_value,
+        })
+    }
+
+    pub fn load(name: &str) -> io::Result<Baseline> {
+        let path = baseline_path(name)?;
+        let file = File::open(&path).map_err(|e| {
+            io::Error::new(e.kind(),
+                           format!("could not read baseline `{}` from {}: {}",
+                                   name, path.display(), e))
+        })?;
+
+        let mut baseline = Baseline::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let tab = match line.rfind('\t') {
+                Some(tab) => tab,
+                None => continue,
+            };
+            let samples = line[tab + 1..]
+                .split(' ')
+                .filter_map(|s| s.parse::<f64>().ok())
+                .collect();
+            baseline.insert(&line[..tab], samples);
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, name: &str) -> io::Result<()> {
+        let path = baseline_path(name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = File::create(&path)?;
+        for (bench, samples) in &self.samples {
+            let samples = samples.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+            writeln!(file, "{}\t{}", bench, samples.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+fn baseline_path(name: &str) -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    let dir = exe.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+    let file_name = exe.file_stem().map(|s| s.to_owned()).unwrap_or_default();
+    Ok(dir.join("baselines").join(name).join(file_name))
+}
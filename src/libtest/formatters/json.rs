@@ -31,32 +31,44 @@ impl<T: Write> JsonFormatter<T> {
         ty: &str,
         name: &str,
         evt: &str,
+        exec_time: Option<&Duration>,
         extra: Option<String>,
     ) -> io::Result<()> {
+        let exec_time_json = match exec_time {
+            Some(exec_time) => format!(r#", "exec_time": {}"#, fmt_secs(*exec_time)),
+            None => String::new(),
+        };
         if let Some(extras) = extra {
             self.write_message(&*format!(
-                r#"{{ "type": "{}", "name": "{}", "event": "{}", {} }}"#,
+                r#"{{ "type": "{}", "name": "{}", "event": "{}"{}, {} }}"#,
                 ty,
                 name,
                 evt,
+                exec_time_json,
                 extras
             ))
         } else {
             self.write_message(&*format!(
-                r#"{{ "type": "{}", "name": "{}", "event": "{}" }}"#,
+                r#"{{ "type": "{}", "name": "{}", "event": "{}"{} }}"#,
                 ty,
                 name,
-                evt
+                evt,
+                exec_time_json
             ))
         }
     }
 }
 
 impl<T: Write> OutputFormatter for JsonFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        let shuffle_seed_json = match shuffle_seed {
+            Some(seed) => format!(r#", "shuffle_seed": {}"#, seed),
+            None => String::new(),
+        };
         self.write_message(&*format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": "{}" }}"#,
-            test_count
+            r#"{{ "type": "suite", "event": "started", "test_count": "{}"{} }}"#,
+            test_count,
+            shuffle_seed_json
         ))
     }
 
@@ -71,10 +83,11 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
         &mut self,
         desc: &TestDesc,
         result: &TestResult,
+        exec_time: Option<&Duration>,
         stdout: &[u8],
     ) -> io::Result<()> {
         match *result {
-            TrOk => self.write_event("test", desc.name.as_slice(), "ok", None),
+            TrOk => self.write_event("test", desc.name.as_slice(), "ok", exec_time, None),
 
             TrFailed => {
                 let extra_data = if stdout.len() > 0 {
@@ -86,7 +99,7 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
                     None
                 };
 
-                self.write_event("test", desc.name.as_slice(), "failed", extra_data)
+                self.write_event("test", desc.name.as_slice(), "failed", exec_time, extra_data)
             }
 
             TrFailedMsg(ref m) => {
@@ -94,14 +107,25 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
                     "test",
                     desc.name.as_slice(),
                     "failed",
+                    exec_time,
                     Some(format!(r#""message": "{}""#, EscapedString(m))),
                 )
             }
 
-            TrIgnored => self.write_event("test", desc.name.as_slice(), "ignored", None),
+            TrIgnored => self.write_event("test", desc.name.as_slice(), "ignored", None, None),
 
             TrAllowedFail => {
-                self.write_event("test", desc.name.as_slice(), "allowed_failure", None)
+                self.write_event("test", desc.name.as_slice(), "allowed_failure", exec_time, None)
+            }
+
+            TrTimedOut(secs) => {
+                self.write_event(
+                    "test",
+                    desc.name.as_slice(),
+                    "timed_out",
+                    exec_time,
+                    Some(format!(r#""timeout": {}"#, secs)),
+                )
             }
 
             TrBench(ref bs) => {
@@ -114,15 +138,28 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
                     format!(r#", "mib_per_second": {}"#, bs.mb_s)
                 };
 
+                let comparison = match bs.comparison {
+                    Some(ref cmp) => format!(
+                        ", \"baseline_median\": {}, \"change_pct\": {:.4}, \
+                         \"p_value\": {:.6}, \"regression\": {}",
+                        cmp.baseline_median as usize,
+                        cmp.change_pct,
+                        cmp.p_value,
+                        cmp.is_regression()
+                    ),
+                    None => "".into(),
+                };
+
                 let line = format!(
                     "{{ \"type\": \"bench\", \
                                 \"name\": \"{}\", \
                                 \"median\": {}, \
-                                \"deviation\": {}{} }}",
+                                \"deviation\": {}{}{} }}",
                     desc.name,
                     median,
                     deviation,
-                    mbps
+                    mbps,
+                    comparison
                 );
 
                 self.write_message(&*line)
@@ -0,0 +1,221 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::*;
+use std::mem;
+
+pub(crate) struct JunitFormatter<T> {
+    out: OutputLocation<T>,
+    run_start: Option<Instant>,
+    results: Vec<(TestDesc, TestResult, Vec<u8>, Duration)>,
+}
+
+impl<T: Write> JunitFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self {
+            out,
+            run_start: None,
+            results: Vec::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
+    fn write_message(&mut self, s: &str) -> io::Result<()> {
+        self.out.write_all(s.as_ref())?;
+        self.out.write_all(b"\n")
+    }
+
+    fn write_testcase(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        stdout: &[u8],
+        duration: Duration,
+    ) -> io::Result<()> {
+        let (classname, name) = split_name(desc.name.as_slice());
+        self.write_message(&*format!(
+            r#"<testcase classname="{}" name="{}" time="{}">"#,
+            EscapedXml(classname),
+            EscapedXml(name),
+            fmt_secs(duration)
+        ))?;
+
+        match *result {
+            TrOk | TrBench(_) => {}
+            TrFailed => self.write_message(r#"<failure type="assert"/>"#)?,
+            TrFailedMsg(ref m) => {
+                self.write_message(&*format!(
+                    r#"<failure type="assert" message="{}"/>"#,
+                    EscapedXml(m)
+                ))?
+            }
+            TrTimedOut(secs) => {
+                self.write_message(&*format!(
+                    "<failure type=\"timeout\" \
+                     message=\"test did not finish within {} seconds\"/>",
+                    secs
+                ))?
+            }
+            TrIgnored => self.write_message("<skipped/>")?,
+            TrAllowedFail => {
+                self.write_message(r#"<skipped message="failed (allowed)"/>"#)?
+            }
+        }
+
+        if !stdout.is_empty() {
+            self.write_message(&*format!(
+                "<system-out>{}</system-out>",
+                EscapedXml(String::from_utf8_lossy(stdout))
+            ))?;
+        }
+
+        self.write_message("</testcase>")
+    }
+
+    fn write_property(&mut self, bench: &str, key: &str, value: usize) -> io::Result<()> {
+        self.write_message(&*format!(
+            r#"<property name="{}.{}" value="{}"/>"#,
+            EscapedXml(bench),
+            key,
+            value
+        ))
+    }
+}
+
+impl<T: Write> OutputFormatter for JunitFormatter<T> {
+    fn write_run_start(&mut self, _: usize, _: Option<u64>) -> io::Result<()> {
+        self.run_start = Some(Instant::now());
+        self.write_message(r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&Duration>,
+        stdout: &[u8],
+    ) -> io::Result<()> {
+        // The XML is written in one go at the end of the run, since the
+        // <testsuite> element carries the totals as attributes.
+        let duration = exec_time.cloned().unwrap_or(Duration::new(0, 0));
+        self.results.push((desc.clone(), result.clone(), stdout.to_vec(), duration));
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // There is no way to represent a warning in JUnit XML.
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let elapsed = self.run_start
+            .map(|start| start.elapsed())
+            .unwrap_or(Duration::new(0, 0));
+
+        self.write_message("<testsuites>")?;
+        self.write_message(&*format!(
+            "<testsuite name=\"test\" package=\"test\" id=\"0\" errors=\"0\" \
+             failures=\"{}\" tests=\"{}\" skipped=\"{}\" time=\"{}\">",
+            state.failed,
+            state.total,
+            state.ignored + state.allowed_fail,
+            fmt_secs(elapsed)
+        ))?;
+
+        let results = mem::replace(&mut self.results, Vec::new());
+
+        {
+            let benches = results
+                .iter()
+                .filter_map(|&(ref desc, ref result, _, _)| match *result {
+                    TrBench(ref bs) => Some((desc, bs)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            if !benches.is_empty() {
+                self.write_message("<properties>")?;
+                for (desc, bs) in benches {
+                    let name = desc.name.as_slice();
+                    let median = bs.ns_iter_summ.median as usize;
+                    let deviation = (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as usize;
+                    self.write_property(name, "median_ns", median)?;
+                    self.write_property(name, "deviation_ns", deviation)?;
+                    if bs.mb_s != 0 {
+                        self.write_property(name, "mb_per_second", bs.mb_s)?;
+                    }
+                }
+                self.write_message("</properties>")?;
+            }
+        }
+
+        for (desc, result, stdout, duration) in results {
+            self.write_testcase(&desc, &result, &stdout, duration)?;
+        }
+
+        self.write_message("</testsuite>")?;
+        self.write_message("</testsuites>")?;
+
+        Ok(state.failed == 0)
+    }
+}
+
+/// Splits `a::b::c` into the class name `a::b` and the test name `c`. Tests at
+/// the root of the crate get the class name `crate`.
+fn split_name(name: &str) -> (&str, &str) {
+    match name.rfind("::") {
+        Some(i) => (&name[..i], &name[i + 2..]),
+        None => ("crate", name),
+    }
+}
+
+/// A formatting utility used to escape text and attribute values in XML.
+/// Control characters that cannot appear in XML 1.0 at all are dropped.
+struct EscapedXml<S: AsRef<str>>(S);
+
+impl<S: AsRef<str>> ::std::fmt::Display for EscapedXml<S> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let mut start = 0;
+
+        for (i, byte) in self.0.as_ref().bytes().enumerate() {
+            let escaped = match byte {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'"' => "&quot;",
+                b'\'' => "&apos;",
+                b'\t' | b'\n' | b'\r' => continue,
+                b'\x00'...b'\x1f' => "",
+                _ => continue,
+            };
+
+            if start < i {
+                f.write_str(&self.0.as_ref()[start..i])?;
+            }
+
+            f.write_str(escaped)?;
+
+            start = i + 1;
+        }
+
+        if start != self.0.as_ref().len() {
+            f.write_str(&self.0.as_ref()[start..])?;
+        }
+
+        Ok(())
+    }
+}
@@ -13,20 +13,28 @@ use super::*;
 mod pretty;
 mod json;
 mod terse;
+mod junit;
 
 pub(crate) use self::pretty::PrettyFormatter;
 pub(crate) use self::json::JsonFormatter;
 pub(crate) use self::terse::TerseFormatter;
+pub(crate) use self::junit::JunitFormatter;
 
 pub(crate) trait OutputFormatter {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()>;
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()>;
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()>;
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()>;
     fn write_result(
         &mut self,
         desc: &TestDesc,
         result: &TestResult,
+        exec_time: Option<&Duration>,
         stdout: &[u8],
     ) -> io::Result<()>;
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool>;
 }
+
+/// Formats a duration as seconds with millisecond precision, e.g. `1.500`.
+pub(crate) fn fmt_secs(duration: Duration) -> String {
+    format!("{}.{:03}", duration.as_secs(), duration.subsec_nanos() / 1_000_000)
+}
@@ -18,6 +18,9 @@ pub(crate) struct PrettyFormatter<T> {
     max_name_len: usize,
 
     is_multithreaded: bool,
+
+    /// Whether to print the execution time of each test
+    report_time: bool,
 }
 
 impl<T: Write> PrettyFormatter<T> {
@@ -26,12 +29,14 @@ impl<T: Write> PrettyFormatter<T> {
         use_color: bool,
         max_name_len: usize,
         is_multithreaded: bool,
+        report_time: bool,
     ) -> Self {
         PrettyFormatter {
             out,
             use_color,
             max_name_len,
             is_multithreaded,
+            report_time,
         }
     }
 
@@ -56,6 +61,10 @@ impl<T: Write> PrettyFormatter<T> {
         self.write_short_result("FAILED (allowed)", term::color::YELLOW)
     }
 
+    pub fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("TIMED OUT", term::color::RED)
+    }
+
     pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -65,8 +74,7 @@ impl<T: Write> PrettyFormatter<T> {
         result: &str,
         color: term::color::Color,
     ) -> io::Result<()> {
-        self.write_pretty(result, color)?;
-        self.write_plain("\n")
+        self.write_pretty(result, color)
     }
 
     pub fn write_pretty(&mut self, word: &str, color: term::color::Color) -> io::Result<()> {
@@ -146,6 +154,19 @@ impl<T: Write> PrettyFormatter<T> {
         Ok(())
     }
 
+    pub fn write_slowest(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        let slowest = state.slowest_tests();
+        if slowest.is_empty() {
+            return Ok(());
+        }
+
+        self.write_plain("\nslowest tests:\n")?;
+        for &&(ref desc, exec_time) in &slowest {
+            self.write_plain(&format!("    {:>9}s {}\n", fmt_secs(exec_time), desc.name))?;
+        }
+        Ok(())
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         self.write_plain(&format!("test {} ... ", name))?;
@@ -155,7 +176,7 @@ impl<T: Write> PrettyFormatter<T> {
 }
 
 impl<T: Write> OutputFormatter for PrettyFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(&mut self, test_count: usize, _: Option<u64>) -> io::Result<()> {
         let noun = if test_count != 1 { "tests" } else { "test" };
         self.write_plain(&format!("\nrunning {} {}\n", test_count, noun))
     }
@@ -172,21 +193,33 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
         Ok(())
     }
 
-    fn write_result(&mut self, desc: &TestDesc, result: &TestResult, _: &[u8]) -> io::Result<()> {
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&Duration>,
+        _: &[u8],
+    ) -> io::Result<()> {
         if self.is_multithreaded {
             self.write_test_name(desc)?;
         }
 
         match *result {
-            TrOk => self.write_ok(),
-            TrFailed | TrFailedMsg(_) => self.write_failed(),
-            TrIgnored => self.write_ignored(),
-            TrAllowedFail => self.write_allowed_fail(),
+            TrOk => self.write_ok()?,
+            TrFailed | TrFailedMsg(_) => self.write_failed()?,
+            TrIgnored => self.write_ignored()?,
+            TrAllowedFail => self.write_allowed_fail()?,
+            TrTimedOut(_) => self.write_timed_out()?,
             TrBench(ref bs) => {
                 self.write_bench()?;
-                self.write_plain(&format!(": {}\n", fmt_bench_samples(bs)))
+                self.write_plain(&format!(": {}", fmt_bench_samples(bs)))?;
             }
         }
+
+        if let (true, Some(exec_time)) = (self.report_time, exec_time) {
+            self.write_plain(&format!(" <{}s>", fmt_secs(*exec_time)))?;
+        }
+        self.write_plain("\n")
     }
 
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -209,6 +242,7 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
         if !success {
             self.write_failures(state)?;
         }
+        self.write_slowest(state)?;
 
         self.write_plain("\ntest result: ")?;
 
@@ -221,7 +255,7 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
 
         let s = if state.allowed_fail > 0 {
             format!(
-                ". {} passed; {} failed ({} allowed); {} ignored; {} measured; {} filtered out\n\n",
+                ". {} passed; {} failed ({} allowed); {} ignored; {} measured; {} filtered out",
                 state.passed,
                 state.failed + state.allowed_fail,
                 state.allowed_fail,
@@ -231,7 +265,7 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
             )
         } else {
             format!(
-                ". {} passed; {} failed; {} ignored; {} measured; {} filtered out\n\n",
+                ". {} passed; {} failed; {} ignored; {} measured; {} filtered out",
                 state.passed,
                 state.failed,
                 state.ignored,
@@ -239,6 +273,10 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
                 state.filtered_out
             )
         };
+        let s = match state.shuffle_seed {
+            Some(seed) => format!("{}; shuffle seed: {}\n\n", s, seed),
+            None => format!("{}\n\n", s),
+        };
 
         self.write_plain(&s)?;
 
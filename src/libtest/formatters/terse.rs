@@ -52,6 +52,10 @@ impl<T: Write> TerseFormatter<T> {
         self.write_short_result("a", term::color::YELLOW)
     }
 
+    pub fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("T", term::color::RED)
+    }
+
     pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -150,6 +154,19 @@ impl<T: Write> TerseFormatter<T> {
         Ok(())
     }
 
+    pub fn write_slowest(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        let slowest = state.slowest_tests();
+        if slowest.is_empty() {
+            return Ok(());
+        }
+
+        self.write_plain("\nslowest tests:\n")?;
+        for &&(ref desc, exec_time) in &slowest {
+            self.write_plain(&format!("    {:>9}s {}\n", fmt_secs(exec_time), desc.name))?;
+        }
+        Ok(())
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         self.write_plain(&format!("test {} ... ", name))?;
@@ -159,7 +176,7 @@ impl<T: Write> TerseFormatter<T> {
 }
 
 impl<T: Write> OutputFormatter for TerseFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(&mut self, test_count: usize, _: Option<u64>) -> io::Result<()> {
         let noun = if test_count != 1 { "tests" } else { "test" };
         self.write_plain(&format!("\nrunning {} {}\n", test_count, noun))
     }
@@ -176,12 +193,19 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
         Ok(())
     }
 
-    fn write_result(&mut self, desc: &TestDesc, result: &TestResult, _: &[u8]) -> io::Result<()> {
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        _: Option<&Duration>,
+        _: &[u8],
+    ) -> io::Result<()> {
         match *result {
             TrOk => self.write_ok(),
             TrFailed | TrFailedMsg(_) => self.write_failed(),
             TrIgnored => self.write_ignored(),
             TrAllowedFail => self.write_allowed_fail(),
+            TrTimedOut(_) => self.write_timed_out(),
             TrBench(ref bs) => {
                 if self.is_multithreaded {
                     self.write_test_name(desc)?;
@@ -208,6 +232,7 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
         if !success {
             self.write_failures(state)?;
         }
+        self.write_slowest(state)?;
 
         self.write_plain("\ntest result: ")?;
 
@@ -220,7 +245,7 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
 
         let s = if state.allowed_fail > 0 {
             format!(
-                ". {} passed; {} failed ({} allowed); {} ignored; {} measured; {} filtered out\n\n",
+                ". {} passed; {} failed ({} allowed); {} ignored; {} measured; {} filtered out",
                 state.passed,
                 state.failed + state.allowed_fail,
                 state.allowed_fail,
@@ -230,7 +255,7 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
             )
         } else {
             format!(
-                ". {} passed; {} failed; {} ignored; {} measured; {} filtered out\n\n",
+                ". {} passed; {} failed; {} ignored; {} measured; {} filtered out",
                 state.passed,
                 state.failed,
                 state.ignored,
@@ -238,6 +263,10 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
                 state.filtered_out
             )
         };
+        let s = match state.shuffle_seed {
+            Some(seed) => format!("{}; shuffle seed: {}\n\n", s, seed),
+            None => format!("{}\n\n", s),
+        };
 
         self.write_plain(&s)?;
 
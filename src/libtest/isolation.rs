@@ -0,0 +1,238 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Running tests in their own process (`--isolation=process`).
+//!
+//! The harness re-executes the test binary with its original arguments and
+//! `__RUST_TEST_INVOKE` set to the name of a single test. The child runs just
+//! that test on its main thread and reports the outcome through its exit
+//! status, while everything it writes to stdout and stderr is piped back to
+//! the harness as the captured output of the test. A test that segfaults,
+//! aborts or exits the process early therefore only fails itself.
+//!
+//! With `panic=abort` a panicking test never gets to report its result
+//! through the exit status. The panic hook of the child writes the result of
+//! a panic on the test's own thread to a file named by `PANIC_RESULT_VAR`,
+//! which the harness reads if the child did not exit normally.
+
+use super::*;
+use std::fs;
+use std::panic;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+/// Environment variable naming the test a spawned child should run.
+pub(crate) const TEST_INVOKE_VAR: &str = "__RUST_TEST_INVOKE";
+
+/// Environment variable naming the file a spawned child writes the result of
+/// a panic on its test thread to.
+const PANIC_RESULT_VAR: &str = "__RUST_TEST_PANIC_RESULT";
+
+/// Distinguishes the panic result files of the children of this process.
+static PANIC_RESULT_FILES: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// Exit codes a child uses to report its result. They are chosen to be
+// unlikely to collide with codes a test passes to `process::exit` itself.
+const TR_OK: i32 = 50;
+const TR_FAILED: i32 = 51;
+
+/// Spawns a child process running the test `desc` and sends its result to
+/// `monitor_ch` once the child has exited or has been killed for running
+/// longer than `timeout` seconds.
+pub(crate) fn run_test_in_process(desc: TestDesc,
+                                  monitor_ch: Sender<MonitorMsg>,
+                                  nocapture: bool,
+                                  timeout: Option<u64>) {
+    let name = desc.name.clone();
+    let runtest = move || {
+        let (test_result, stdout) = match spawn_and_wait(&desc, nocapture, timeout) {
+            Ok(res) => res,
+            Err(e) => {
+                let msg = format!("failed to spawn a process for the test: {}", e);
+                (TrFailedMsg(msg), Vec::new())
+            }
+        };
+        monitor_ch
+            .send((desc.clone(), test_result, stdout))
+            .unwrap();
+    };
+
+    let cfg = thread::Builder::new().name(name.as_slice().to_owned());
+    cfg.spawn(runtest).unwrap();
+}
+
+fn spawn_and_wait(desc: &TestDesc,
+                  nocapture: bool,
+                  timeout: Option<u64>) -> io::Result<(TestResult, Vec<u8>)> {
+    let panic_result = env::temp_dir().join(format!(
+        "rust-test-{}-{}",
+        process::id(),
+        PANIC_RESULT_FILES.fetch_add(1, Ordering::SeqCst)
+    ));
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(env::args_os().skip(1))
+        .env(TEST_INVOKE_VAR, desc.name.as_slice())
+        .env(PANIC_RESULT_VAR, &panic_result)
+        .stdin(Stdio::null());
+    if !nocapture {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let mut child = command.spawn()?;
+
+    // Both pipes are drained on their own threads so that a chatty test
+    // cannot block on a full pipe while we are waiting for it to exit.
+    let data = Arc::new(Mutex::new(Vec::new()));
+    let mut readers = Vec::new();
+    if let Some(pipe) = child.stdout.take() {
+        readers.push(drain(pipe, data.clone()));
+    }
+    if let Some(pipe) = child.stderr.take() {
+        readers.push(drain(pipe, data.clone()));
+    }
+
+    let status = match timeout {
+        None => Some(child.wait()?),
+        Some(secs) => wait_timeout(&mut child, Duration::from_secs(secs))?,
+    };
+
+    let test_result = match status {
+        Some(status) => {
+            for reader in readers {
+                let _ = reader.join();
+            }
+            calc_process_result(desc, status, fs::read_to_string(&panic_result).ok())
+        }
+        None => {
+            // Don't wait for the readers here: processes the test left
+            // behind may still hold on to the pipes.
+            child.kill()?;
+            child.wait()?;
+            TrTimedOut(timeout.unwrap())
+        }
+    };
+
+    let _ = fs::remove_file(&panic_result);
+    let stdout = data.lock().unwrap().to_vec();
+    Ok((test_result, stdout))
+}
+
+fn drain<R: Read + Send + 'static>(mut pipe: R,
+                                   data: Arc<Mutex<Vec<u8>>>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = [0; 4096];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => data.lock().unwrap().extend_from_slice(&buf[..n]),
+            }
+        }
+    })
+}
+
+fn wait_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// The result of a child that exited with `status`. `panic_result` is the
+/// content of its panic result file, if it wrote one.
+fn calc_process_result(desc: &TestDesc,
+                       status: ExitStatus,
+                       panic_result: Option<String>) -> TestResult {
+    let test_result = match (status.code(), panic_result) {
+        (Some(TR_OK), _) => return TrOk,
+        (Some(TR_FAILED), _) => TrFailed,
+        // The test thread panicked and the process aborted instead of
+        // unwinding.
+        (_, Some(panic_result)) => {
+            let mut lines = panic_result.splitn(2, '\n');
+            match (lines.next(), lines.next()) {
+                (Some("ok"), _) => return TrOk,
+                (_, Some(msg)) if !msg.is_empty() => TrFailedMsg(msg.to_owned()),
+                _ => TrFailed,
+            }
+        }
+        // Signals and unexpected exit codes, e.g. from a test calling
+        // `process::exit` or `process::abort`.
+        _ => TrFailedMsg(format!("test process terminated abnormally ({})", status)),
+    };
+
+    if desc.allow_fail {
+        TrAllowedFail
+    } else {
+        test_result
+    }
+}
+
+/// Runs the test called `name` in the current process and exits with a status
+/// describing its result. This is the child side of `run_test_in_process`.
+pub(crate) fn run_test_in_spawned_subprocess(name: &str, tests: Vec<TestDescAndFn>) -> ! {
+    let TestDescAndFn { desc, testfn } = convert_benchmarks_to_tests(tests)
+        .into_iter()
+        .find(|test| test.desc.name.as_slice() == name)
+        .unwrap_or_else(|| panic!("no test named `{}` to run in this process", name));
+
+    // With `panic=abort` a panic on the test thread never returns to us, so
+    // the hook leaves the result of such a panic for the harness. If the
+    // panic unwinds instead, the result we exit with below wins. Panics on
+    // other threads are left to the test to deal with, as they are when it
+    // runs on a thread of the harness.
+    let panic_result = env::var_os(PANIC_RESULT_VAR);
+    env::remove_var(PANIC_RESULT_VAR);
+    if let Some(panic_result) = panic_result {
+        let builtin_panic_hook = panic::take_hook();
+        let hook_desc = desc.clone();
+        let test_thread = thread::current().id();
+        panic::set_hook(Box::new(move |info| {
+            builtin_panic_hook(info);
+            if thread::current().id() == test_thread {
+                let contents = match calc_result(&hook_desc, Err(info.payload())) {
+                    TrOk => "ok\n".to_owned(),
+                    TrFailedMsg(msg) => format!("failed\n{}", msg),
+                    _ => "failed\n".to_owned(),
+                };
+                let _ = fs::write(&panic_result, contents);
+            }
+        }));
+    }
+
+    let result = match testfn {
+        StaticTestFn(f) => catch_unwind(AssertUnwindSafe(|| __rust_begin_short_backtrace(f))),
+        DynTestFn(f) => catch_unwind(AssertUnwindSafe(|| __rust_begin_short_backtrace(f))),
+        StaticBenchFn(..) | DynBenchFn(..) => unreachable!(),
+    };
+
+    exit_with_result(calc_result(&desc, result.as_ref().map(|_| ()).map_err(|e| &**e)))
+}
+
+fn exit_with_result(test_result: TestResult) -> ! {
+    let code = match test_result {
+        TrOk => TR_OK,
+        TrFailedMsg(msg) => {
+            eprintln!("note: {}", msg);
+            TR_FAILED
+        }
+        _ => TR_FAILED,
+    };
+
+    // `process::exit` does not run destructors, so flush what the test
+    // printed before leaving.
+    let _ = io::stdout().flush();
+    process::exit(code)
+}
@@ -72,7 +72,7 @@ use std::process::Termination;
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Instant, Duration};
+use std::time::{Instant, Duration, SystemTime, UNIX_EPOCH};
 use std::borrow::Cow;
 use std::process;
 
@@ -89,8 +89,12 @@ pub mod test {
 
 pub mod stats;
 mod formatters;
+mod isolation;
+mod baseline;
 
-use formatters::{OutputFormatter, PrettyFormatter, TerseFormatter, JsonFormatter};
+use baseline::{Baseline, BenchComparison};
+use formatters::{OutputFormatter, PrettyFormatter, TerseFormatter, JsonFormatter,
+                 JunitFormatter};
 
 // The name of a test. By convention this follows the rules for rust
 // paths; i.e. it should be a series of identifiers separated by double
@@ -203,6 +207,7 @@ impl fmt::Debug for TestFn {
 pub struct Bencher {
     mode: BenchMode,
     summary: Option<stats::Summary>,
+    samples: Vec<f64>,
     pub bytes: u64,
 }
 
@@ -227,6 +232,9 @@ pub struct TestDesc {
     pub ignore: bool,
     pub should_panic: ShouldPanic,
     pub allow_fail: bool,
+    /// Number of seconds after which the test is reported as timed out,
+    /// overriding `--test-timeout`.
+    pub timeout: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -267,6 +275,12 @@ impl Options {
 // The default console test runner. It accepts the command line
 // arguments and a vector of test_descs.
 pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Options) {
+    // We are the child of a harness running with `--isolation=process`.
+    if let Ok(name) = env::var(isolation::TEST_INVOKE_VAR) {
+        env::remove_var(isolation::TEST_INVOKE_VAR);
+        isolation::run_test_in_spawned_subprocess(&name, tests);
+    }
+
     let mut opts = match parse_opts(args) {
         Some(Ok(o)) => o,
         Some(Err(msg)) => {
@@ -343,6 +357,15 @@ pub enum OutputFormat {
     Pretty,
     Terse,
     Json,
+    Junit,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Isolation {
+    /// Run each test on a thread of the harness process.
+    Thread,
+    /// Run each test in a freshly spawned copy of the test binary.
+    Process,
 }
 
 #[derive(Debug)]
@@ -359,6 +382,16 @@ pub struct TestOpts {
     pub format: OutputFormat,
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
+    pub test_timeout: Option<u64>,
+    pub isolation: Isolation,
+    pub shuffle: bool,
+    pub shuffle_seed: Option<u64>,
+    pub report_time: bool,
+    pub slowest: Option<usize>,
+    pub shard_index: usize,
+    pub shard_count: usize,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
     pub options: Options,
 }
 
@@ -378,6 +411,16 @@ impl TestOpts {
             format: OutputFormat::Pretty,
             test_threads: None,
             skip: vec![],
+            test_timeout: None,
+            isolation: Isolation::Thread,
+            shuffle: false,
+            shuffle_seed: None,
+            report_time: false,
+            slowest: None,
+            shard_index: 0,
+            shard_count: 1,
+            save_baseline: None,
+            baseline: None,
             options: Options::new(),
         }
     }
@@ -413,6 +456,21 @@ fn optgroups() -> getopts::Options {
                                      in parallel",
             "n_threads",
         )
+        .optopt(
+            "",
+            "test-timeout",
+            "Fail tests that run for longer than SECS seconds \
+                                     (unstable)",
+            "SECS",
+        )
+        .optopt(
+            "",
+            "isolation",
+            "Configure how tests are isolated from each other (unstable):
+            thread  = run each test on its own thread (default);
+            process = run each test in its own process",
+            "thread|process",
+        )
         .optmulti(
             "",
             "skip",
@@ -426,6 +484,54 @@ fn optgroups() -> getopts::Options {
             "Display one character per test instead of one line. \
                                 Alias to --format=terse",
         )
+        .optflag(
+            "",
+            "shuffle",
+            "Run tests in random order (unstable)",
+        )
+        .optopt(
+            "",
+            "shuffle-seed",
+            "Run tests in random order, using SEED to determine the order \
+                                  (unstable)",
+            "SEED",
+        )
+        .optflag(
+            "",
+            "report-time",
+            "Show the execution time of each test (unstable)",
+        )
+        .optopt(
+            "",
+            "slowest",
+            "Print the N slowest tests at the end of the run (unstable)",
+            "N",
+        )
+        .optopt(
+            "",
+            "shard-index",
+            "Only run the tests of shard INDEX, counting from 0 (unstable)",
+            "INDEX",
+        )
+        .optopt(
+            "",
+            "shard-count",
+            "Split the tests into N disjoint shards (unstable)",
+            "N",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the results of the benchmarks as the baseline NAME (unstable)",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the results of the benchmarks to the baseline NAME \
+                                  (unstable)",
+            "NAME",
+        )
         .optflag(
             "",
             "exact",
@@ -446,8 +552,9 @@ fn optgroups() -> getopts::Options {
             "Configure formatting of output:
             pretty = Print verbose output;
             terse  = Display one character per test;
-            json   = Output a json document",
-            "pretty|terse|json",
+            json   = Output a json document;
+            junit  = Output a JUnit XML document",
+            "pretty|terse|json|junit",
         )
         .optopt(
             "Z",
@@ -471,6 +578,21 @@ By default, all tests are run in parallel. This can be altered with the
 --test-threads flag or the RUST_TEST_THREADS environment variable when running
 tests (set it to 1).
 
+A test binary can be split across several machines with --shard-count N and
+--shard-index I: every test ends up in exactly one of the N shards, and only
+the tests of shard I (counting from 0) are listed or run. The split only
+depends on the names of the tests left after filtering, so all machines must
+use the same filters.
+
+The results of benchmarks can be saved with --save-baseline NAME. A later run
+with --baseline NAME compares each benchmark with the saved results, and marks
+it as regressed or improved if the change of its median is both larger than 2%
+and statistically significant according to Welch's t-test.
+
+Tests are started in alphabetical order. With --shuffle they are started in a
+random order instead, and the seed used is printed at the end of the run so that
+the same order can be reproduced with --shuffle-seed.
+
 All tests have their standard output and standard error captured by default.
 This can be overridden with the --nocapture flag or setting RUST_TEST_NOCAPTURE
 environment variable to a value other than "0". Logging is not captured by default.
@@ -488,7 +610,9 @@ Test Attributes:
     #[ignore]      - When applied to a function which is already attributed as a
                      test, then the test runner will ignore these tests during
                      normal test runs. Running with --ignored will run these
-                     tests."#,
+                     tests.
+    #[test_timeout = "SECS"] - Fail the test if it runs for longer than SECS
+                     seconds, overriding --test-timeout."#,
         usage = options.usage(&message)
     );
 }
@@ -578,6 +702,153 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         None => None,
     };
 
+    let test_timeout = match matches.opt_str("test-timeout") {
+        Some(secs) => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"test-timeout\" flag is only accepted on the nightly compiler"
+                        .into(),
+                ));
+            }
+            match secs.parse::<u64>() {
+                Ok(0) => return Some(Err(format!("argument for --test-timeout must not be 0"))),
+                Ok(n) => Some(n),
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --test-timeout must be a number > 0 \
+                                             (error: {})",
+                        e
+                    )))
+                }
+            }
+        }
+        None => None,
+    };
+
+    let isolation = match matches.opt_str("isolation").as_ref().map(|s| &**s) {
+        Some("thread") | None => Isolation::Thread,
+        Some("process") => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"process\" isolation mode is only accepted on the nightly compiler"
+                        .into(),
+                ));
+            }
+            Isolation::Process
+        }
+
+        Some(v) => {
+            return Some(Err(format!(
+                "argument for --isolation must be thread or process (was \
+                                     {})",
+                v
+            )))
+        }
+    };
+
+    let shuffle = matches.opt_present("shuffle");
+    let shuffle_seed = match matches.opt_str("shuffle-seed") {
+        Some(n_str) => {
+            match n_str.parse::<u64>() {
+                Ok(n) => Some(n),
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --shuffle-seed must be a number \
+                                             (error: {})",
+                        e
+                    )))
+                }
+            }
+        }
+        None => None,
+    };
+    if (shuffle || shuffle_seed.is_some()) && !allow_unstable {
+        return Some(Err(
+            "The \"shuffle\" and \"shuffle-seed\" flags are only accepted on the nightly compiler"
+                .into(),
+        ));
+    }
+
+    let report_time = matches.opt_present("report-time");
+    let slowest = match matches.opt_str("slowest") {
+        Some(n_str) => {
+            match n_str.parse::<usize>() {
+                Ok(n) => Some(n),
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --slowest must be a number \
+                                             (error: {})",
+                        e
+                    )))
+                }
+            }
+        }
+        None => None,
+    };
+    if (report_time || slowest.is_some()) && !allow_unstable {
+        return Some(Err(
+            "The \"report-time\" and \"slowest\" flags are only accepted on the nightly compiler"
+                .into(),
+        ));
+    }
+
+    let (shard_index, shard_count) = match (matches.opt_str("shard-index"),
+                                            matches.opt_str("shard-count")) {
+        (None, None) => (0, 1),
+        (Some(index), Some(count)) => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"shard-index\" and \"shard-count\" flags are only accepted on \
+                     the nightly compiler"
+                        .into(),
+                ));
+            }
+            let count = match count.parse::<usize>() {
+                Ok(0) => return Some(Err(format!("argument for --shard-count must not be 0"))),
+                Ok(n) => n,
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --shard-count must be a number > 0 \
+                                             (error: {})",
+                        e
+                    )))
+                }
+            };
+            let index = match index.parse::<usize>() {
+                Ok(n) if n < count => n,
+                Ok(n) => {
+                    return Some(Err(format!(
+                        "argument for --shard-index must be less than --shard-count \
+                                             (was {})",
+                        n
+                    )))
+                }
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --shard-index must be a number \
+                                             (error: {})",
+                        e
+                    )))
+                }
+            };
+            (index, count)
+        }
+        _ => {
+            return Some(Err(
+                "--shard-index and --shard-count must be used together".into(),
+            ))
+        }
+    };
+
+    let save_baseline = matches.opt_str("save-baseline");
+    let baseline = matches.opt_str("baseline");
+    if (save_baseline.is_some() || baseline.is_some()) && !allow_unstable {
+        return Some(Err(
+            "The \"save-baseline\" and \"baseline\" flags are only accepted on the nightly compiler"
+                .into(),
+        ));
+    }
+
     let color = match matches.opt_str("color").as_ref().map(|s| &**s) {
         Some("auto") | None => AutoColor,
         Some("always") => AlwaysColor,
@@ -605,10 +876,19 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
             }
             OutputFormat::Json
         }
+        Some("junit") => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"junit\" format is only accepted on the nightly compiler"
+                        .into(),
+                ));
+            }
+            OutputFormat::Junit
+        }
 
         Some(v) => {
             return Some(Err(format!(
-                "argument for --format must be pretty, terse, or json (was \
+                "argument for --format must be pretty, terse, json, or junit (was \
                                      {})",
                 v
             )))
@@ -628,6 +908,16 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         format,
         test_threads,
         skip: matches.opt_strs("skip"),
+        test_timeout,
+        isolation,
+        shuffle,
+        shuffle_seed,
+        report_time,
+        slowest,
+        shard_index,
+        shard_count,
+        save_baseline,
+        baseline,
         options: Options::new(),
     };
 
@@ -637,7 +927,9 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
 #[derive(Clone, PartialEq)]
 pub struct BenchSamples {
     ns_iter_summ: stats::Summary,
+    ns_iter_samples: Vec<f64>,
     mb_s: usize,
+    comparison: Option<BenchComparison>,
 }
 
 #[derive(Clone, PartialEq)]
@@ -648,6 +940,7 @@ pub enum TestResult {
     TrIgnored,
     TrAllowedFail,
     TrBench(BenchSamples),
+    TrTimedOut(u64),
 }
 
 unsafe impl Send for TestResult {}
@@ -685,6 +978,11 @@ struct ConsoleTestState {
     metrics: MetricMap,
     failures: Vec<(TestDesc, Vec<u8>)>,
     not_failures: Vec<(TestDesc, Vec<u8>)>,
+    shuffle_seed: Option<u64>,
+    exec_times: Vec<(TestDesc, Duration)>,
+    slowest: Option<usize>,
+    baseline: Option<Baseline>,
+    bench_samples: Baseline,
     options: Options,
 }
 
@@ -694,6 +992,10 @@ impl ConsoleTestState {
             Some(ref path) => Some(File::create(path)?),
             None => None,
         };
+        let baseline = match opts.baseline {
+            Some(ref name) => Some(Baseline::load(name)?),
+            None => None,
+        };
 
         Ok(ConsoleTestState {
             log_out,
@@ -707,6 +1009,11 @@ impl ConsoleTestState {
             metrics: MetricMap::new(),
             failures: Vec::new(),
             not_failures: Vec::new(),
+            shuffle_seed: None,
+            exec_times: Vec::new(),
+            slowest: opts.slowest,
+            baseline,
+            bench_samples: Baseline::new(),
             options: opts.options,
         })
     }
@@ -729,6 +1036,7 @@ impl ConsoleTestState {
                 TrIgnored => "ignored".to_owned(),
                 TrAllowedFail => "failed (allowed)".to_owned(),
                 TrBench(ref bs) => fmt_bench_samples(bs),
+                TrTimedOut(secs) => format!("timed out after {}s", secs),
             },
             test.name
         ))
@@ -737,6 +1045,21 @@ impl ConsoleTestState {
     fn current_test_count(&self) -> usize {
         self.passed + self.failed + self.ignored + self.measured + self.allowed_fail
     }
+
+    /// The tests to list in the `--slowest` summary, slowest first.
+    fn slowest_tests(&self) -> Vec<&(TestDesc, Duration)> {
+        let n = match self.slowest {
+            Some(n) if n > 0 => n,
+            _ => return Vec::new(),
+        };
+        let mut tests = self.exec_times.iter().collect::<Vec<_>>();
+        tests.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| a.0.name.as_slice().cmp(b.0.name.as_slice()))
+        });
+        tests.truncate(n);
+        tests
+    }
 }
 
 // Format a number with thousands separators
@@ -782,6 +1105,16 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
             .write_fmt(format_args!(" = {} MB/s", bs.mb_s))
             .unwrap();
     }
+    if let Some(ref cmp) = bs.comparison {
+        output
+            .write_fmt(format_args!(
+                " ({:+.2}% vs baseline, p = {:.3}: {})",
+                cmp.change_pct,
+                cmp.p_value,
+                cmp.verdict()
+            ))
+            .unwrap();
+    }
     output
 }
 
@@ -853,16 +1186,27 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
     ) -> io::Result<()> {
 
         match (*event).clone() {
-            TeFiltered(ref filtered_tests) => {
+            TeFiltered(ref filtered_tests, shuffle_seed) => {
                 st.total = filtered_tests.len();
-                out.write_run_start(filtered_tests.len())
+                st.shuffle_seed = shuffle_seed;
+                out.write_run_start(filtered_tests.len(), shuffle_seed)
             }
             TeFilteredOut(filtered_out) => Ok(st.filtered_out = filtered_out),
             TeWait(ref test) => out.write_test_start(test),
             TeTimeout(ref test) => out.write_timeout(test),
-            TeResult(test, result, stdout) => {
+            TeResult(test, mut result, exec_time, stdout) => {
+                if let TrBench(ref mut bs) = result {
+                    let name = test.name.as_slice();
+                    if let Some(ref baseline) = st.baseline {
+                        bs.comparison = baseline.compare(name, &bs.ns_iter_samples);
+                    }
+                    st.bench_samples.insert(name, bs.ns_iter_samples.clone());
+                }
                 st.write_log_result(&test, &result)?;
-                out.write_result(&test, &result, &*stdout)?;
+                out.write_result(&test, &result, exec_time.as_ref(), &*stdout)?;
+                if let Some(exec_time) = exec_time {
+                    st.exec_times.push((test.clone(), exec_time));
+                }
                 match result {
                     TrOk => {
                         st.passed += 1;
@@ -888,6 +1232,15 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
                         stdout.extend_from_slice(format!("note: {}", msg).as_bytes());
                         st.failures.push((test, stdout));
                     }
+                    TrTimedOut(secs) => {
+                        st.failed += 1;
+                        let mut stdout = stdout;
+                        stdout.extend_from_slice(
+                            format!("note: test did not finish within {} seconds", secs)
+                                .as_bytes(),
+                        );
+                        st.failures.push((test, stdout));
+                    }
                 }
                 Ok(())
             }
@@ -913,6 +1266,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
             use_color(opts),
             max_name_len,
             is_multithreaded,
+            opts.report_time,
         )),
         OutputFormat::Terse => Box::new(TerseFormatter::new(
             output,
@@ -921,6 +1275,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
             is_multithreaded,
         )),
         OutputFormat::Json => Box::new(JsonFormatter::new(output)),
+        OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
     };
     let mut st = ConsoleTestState::new(opts)?;
     fn len_if_padded(t: &TestDescAndFn) -> usize {
@@ -934,6 +1289,10 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
 
     assert!(st.current_test_count() == st.total);
 
+    if let Some(ref name) = opts.save_baseline {
+        st.bench_samples.save(name)?;
+    }
+
     return out.write_run_finish(&st);
 }
 
@@ -944,6 +1303,7 @@ fn should_sort_failures_before_printing_them() {
         ignore: false,
         should_panic: ShouldPanic::No,
         allow_fail: false,
+        timeout: None,
     };
 
     let test_b = TestDesc {
@@ -951,9 +1311,10 @@ fn should_sort_failures_before_printing_them() {
         ignore: false,
         should_panic: ShouldPanic::No,
         allow_fail: false,
+        timeout: None,
     };
 
-    let mut out = PrettyFormatter::new(Raw(Vec::new()), false, 10, false);
+    let mut out = PrettyFormatter::new(Raw(Vec::new()), false, 10, false, false);
 
     let st = ConsoleTestState {
         log_out: None,
@@ -968,6 +1329,11 @@ fn should_sort_failures_before_printing_them() {
         failures: vec![(test_b, Vec::new()), (test_a, Vec::new())],
         options: Options::new(),
         not_failures: Vec::new(),
+        shuffle_seed: None,
+        exec_times: Vec::new(),
+        slowest: None,
+        baseline: None,
+        bench_samples: Baseline::new(),
     };
 
     out.write_failures(&st).unwrap();
@@ -981,6 +1347,54 @@ fn should_sort_failures_before_printing_them() {
     assert!(apos < bpos);
 }
 
+#[test]
+fn junit_output_escapes_failure_messages() {
+    let test = TestDesc {
+        name: StaticTestName("module::a"),
+        ignore: false,
+        should_panic: ShouldPanic::No,
+        allow_fail: false,
+        timeout: None,
+    };
+
+    let mut out = JunitFormatter::new(Raw(Vec::new()));
+
+    let st = ConsoleTestState {
+        log_out: None,
+        total: 1,
+        passed: 0,
+        failed: 1,
+        ignored: 0,
+        allowed_fail: 0,
+        filtered_out: 0,
+        measured: 0,
+        metrics: MetricMap::new(),
+        failures: Vec::new(),
+        options: Options::new(),
+        not_failures: Vec::new(),
+        shuffle_seed: None,
+        exec_times: Vec::new(),
+        slowest: None,
+        baseline: None,
+        bench_samples: Baseline::new(),
+    };
+
+    out.write_run_start(1, None).unwrap();
+    out.write_test_start(&test).unwrap();
+    let exec_time = Duration::from_millis(1500);
+    out.write_result(&test, &TrFailedMsg("1 < 2 & \"x\"".to_owned()), Some(&exec_time), b"")
+        .unwrap();
+    out.write_run_finish(&st).unwrap();
+    let s = match out.output_location() {
+        &Raw(ref m) => String::from_utf8_lossy(&m[..]),
+        &Pretty(_) => unreachable!(),
+    };
+
+    assert!(s.contains(r#"failures="1" tests="1""#));
+    assert!(s.contains(r#"<testcase classname="module" name="a" time="1.500">"#));
+    assert!(s.contains(r#"message="1 &lt; 2 &amp; &quot;x&quot;""#));
+}
+
 fn use_color(opts: &TestOpts) -> bool {
     match opts.color {
         AutoColor => !opts.nocapture && stdout_isatty(),
@@ -1020,9 +1434,10 @@ fn stdout_isatty() -> bool {
 
 #[derive(Clone)]
 pub enum TestEvent {
-    TeFiltered(Vec<TestDesc>),
+    TeFiltered(Vec<TestDesc>, Option<u64>),
     TeWait(TestDesc),
-    TeResult(TestDesc, TestResult, Vec<u8>),
+    /// A finished test, with the time it took to run unless it was ignored.
+    TeResult(TestDesc, TestResult, Option<Duration>, Vec<u8>),
     TeTimeout(TestDesc),
     TeFilteredOut(usize),
 }
@@ -1043,7 +1458,7 @@ pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F)
 where
     F: FnMut(TestEvent) -> io::Result<()>,
 {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::mpsc::RecvTimeoutError;
 
     let tests_len = tests.len();
@@ -1067,7 +1482,8 @@ where
 
     let filtered_descs = filtered_tests.iter().map(|t| t.desc.clone()).collect();
 
-    callback(TeFiltered(filtered_descs))?;
+    let shuffle_seed = get_shuffle_seed(opts);
+    callback(TeFiltered(filtered_descs, shuffle_seed))?;
 
     let (filtered_tests, filtered_benchs): (Vec<_>, _) =
         filtered_tests.into_iter().partition(|e| match e.testfn {
@@ -1078,12 +1494,21 @@ where
     let concurrency = opts.test_threads.unwrap_or_else(get_concurrency);
 
     let mut remaining = filtered_tests;
+    if let Some(seed) = shuffle_seed {
+        shuffle_tests(seed, &mut remaining);
+    }
     remaining.reverse();
     let mut pending = 0;
 
     let (tx, rx) = channel::<MonitorMsg>();
 
     let mut running_tests: HashMap<TestDesc, Instant> = HashMap::new();
+    // Instants at which tests with a time limit are reported as timed out.
+    let mut deadlines: HashMap<TestDesc, Instant> = HashMap::new();
+    // Tests that were reported as timed out while their thread kept running;
+    // the result they eventually send is dropped.
+    let mut abandoned: HashSet<TestDesc> = HashSet::new();
+    let mut start_times: HashMap<TestDesc, Instant> = HashMap::new();
 
     fn get_timed_out_tests(running_tests: &mut HashMap<TestDesc, Instant>) -> Vec<TestDesc> {
         let now = Instant::now();
@@ -1101,6 +1526,16 @@ where
         timed_out
     };
 
+    fn calc_exec_time(start_times: &mut HashMap<TestDesc, Instant>,
+                      desc: &TestDesc,
+                      result: &TestResult) -> Option<Duration> {
+        let start = start_times.remove(desc);
+        match *result {
+            TrIgnored => None,
+            _ => start.map(|start| start.elapsed()),
+        }
+    }
+
     fn calc_timeout(running_tests: &HashMap<TestDesc, Instant>) -> Option<Duration> {
         running_tests.values().min().map(|next_timeout| {
             let now = Instant::now();
@@ -1112,61 +1547,113 @@ where
         })
     };
 
-    if concurrency == 1 {
-        while !remaining.is_empty() {
-            let test = remaining.pop().unwrap();
-            callback(TeWait(test.desc.clone()))?;
-            run_test(opts, !opts.run_tests, test, tx.clone());
-            let (test, result, stdout) = rx.recv().unwrap();
-            callback(TeResult(test, result, stdout))?;
+    let mut warned_about_abandoned = false;
+    while pending > 0 || !remaining.is_empty() {
+        // Timed out tests keep their thread, and so their slot, until they
+        // finish. Once every slot is taken by one, the remaining tests still
+        // run one at a time, alongside the timed out ones.
+        let slots = cmp::max(concurrency.saturating_sub(abandoned.len()), 1);
+        if abandoned.len() >= concurrency && !remaining.is_empty() && !warned_about_abandoned {
+            eprintln!("warning: running the remaining tests while timed out tests are still \
+                       running; use --isolation=process to kill timed out tests");
+            warned_about_abandoned = true;
         }
-    } else {
-        while pending > 0 || !remaining.is_empty() {
-            while pending < concurrency && !remaining.is_empty() {
-                let test = remaining.pop().unwrap();
-                let timeout = Instant::now() + Duration::from_secs(TEST_WARN_TIMEOUT_S);
+
+        while pending < slots && !remaining.is_empty() {
+            let test = remaining.pop().unwrap();
+            let now = Instant::now();
+            // When running tests serially the name of the running test is
+            // already on screen, so there is no need to warn about it.
+            if concurrency > 1 {
+                let timeout = now + Duration::from_secs(TEST_WARN_TIMEOUT_S);
                 running_tests.insert(test.desc.clone(), timeout);
-                callback(TeWait(test.desc.clone()))?; //here no pad
-                run_test(opts, !opts.run_tests, test, tx.clone());
-                pending += 1;
             }
+            // Test processes are killed by the thread waiting on them, so
+            // only tests running on a thread of our own need a deadline.
+            if opts.isolation == Isolation::Thread {
+                if let Some(secs) = test_timeout(opts, &test.desc) {
+                    deadlines.insert(test.desc.clone(), now + Duration::from_secs(secs));
+                }
+            }
+            start_times.insert(test.desc.clone(), now);
+            callback(TeWait(test.desc.clone()))?; //here no pad
+            run_test(opts, !opts.run_tests, test, tx.clone());
+            pending += 1;
+        }
 
-            let mut res;
-            loop {
-                if let Some(timeout) = calc_timeout(&running_tests) {
-                    res = rx.recv_timeout(timeout);
-                    for test in get_timed_out_tests(&mut running_tests) {
-                        callback(TeTimeout(test))?;
-                    }
-                    if res != Err(RecvTimeoutError::Timeout) {
-                        break;
-                    }
-                } else {
-                    res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
+        let mut res;
+        loop {
+            let timeout = calc_timeout(&running_tests)
+                .into_iter()
+                .chain(calc_timeout(&deadlines))
+                .min();
+            if let Some(timeout) = timeout {
+                res = rx.recv_timeout(timeout);
+                for test in get_timed_out_tests(&mut running_tests) {
+                    callback(TeTimeout(test))?;
+                }
+                let mut reported = false;
+                for test in get_timed_out_tests(&mut deadlines) {
+                    // There is no way to stop the thread running the test, so
+                    // report it now and ignore its result. It keeps its slot
+                    // until it finishes.
+                    running_tests.remove(&test);
+                    abandoned.insert(test.clone());
+                    let result = TrTimedOut(test_timeout(opts, &test).unwrap());
+                    let exec_time = calc_exec_time(&mut start_times, &test, &result);
+                    callback(TeResult(test, result, exec_time, Vec::new()))?;
+                    pending -= 1;
+                    reported = true;
+                }
+                if res != Err(RecvTimeoutError::Timeout) || reported {
                     break;
                 }
+            } else {
+                res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
+                break;
             }
+        }
 
-            let (desc, result, stdout) = res.unwrap();
-            running_tests.remove(&desc);
+        if res == Err(RecvTimeoutError::Timeout) {
+            continue;
+        }
 
-            callback(TeResult(desc, result, stdout))?;
-            pending -= 1;
+        let (desc, result, stdout) = res.unwrap();
+        if abandoned.remove(&desc) {
+            continue;
         }
+        running_tests.remove(&desc);
+        deadlines.remove(&desc);
+        let exec_time = calc_exec_time(&mut start_times, &desc, &result);
+
+        callback(TeResult(desc, result, exec_time, stdout))?;
+        pending -= 1;
     }
 
     if opts.bench_benchmarks {
         // All benchmarks run at the end, in serial.
         for b in filtered_benchs {
+            start_times.insert(b.desc.clone(), Instant::now());
             callback(TeWait(b.desc.clone()))?;
             run_test(opts, false, b, tx.clone());
-            let (test, result, stdout) = rx.recv().unwrap();
-            callback(TeResult(test, result, stdout))?;
+            let (test, result, stdout) = loop {
+                let (test, result, stdout) = rx.recv().unwrap();
+                if !abandoned.remove(&test) {
+                    break (test, result, stdout);
+                }
+            };
+            let exec_time = calc_exec_time(&mut start_times, &test, &result);
+            callback(TeResult(test, result, exec_time, stdout))?;
         }
     }
     Ok(())
 }
 
+/// The time limit of a test in seconds, if it has one.
+fn test_timeout(opts: &TestOpts, desc: &TestDesc) -> Option<u64> {
+    desc.timeout.or(opts.test_timeout)
+}
+
 #[allow(deprecated)]
 fn get_concurrency() -> usize {
     return match env::var("RUST_TEST_THREADS") {
@@ -1358,9 +1845,52 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         t1.desc.name.as_slice().cmp(t2.desc.name.as_slice())
     });
 
+    // Only keep our shard. Dealing out the sorted tests round-robin keeps the
+    // shards balanced and makes the split independent of anything but the
+    // test names.
+    if opts.shard_count > 1 {
+        filtered = filtered
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| i % opts.shard_count == opts.shard_index)
+            .map(|(_, test)| test)
+            .collect();
+    }
+
     filtered
 }
 
+fn get_shuffle_seed(opts: &TestOpts) -> Option<u64> {
+    match opts.shuffle_seed {
+        Some(seed) => Some(seed),
+        None if opts.shuffle => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::new(0, 0));
+            Some(now.as_secs().wrapping_mul(1_000_000_000) ^ now.subsec_nanos() as u64)
+        }
+        None => None,
+    }
+}
+
+/// Permutes `tests` with a Fisher-Yates shuffle driven by a SplitMix64
+/// generator seeded with `seed`. The generator is implemented here rather
+/// than taken from the OS so that a seed reproduces the same order on every
+/// platform, given the same (sorted) list of tests.
+pub fn shuffle_tests(seed: u64, tests: &mut [TestDescAndFn]) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    for i in (1..tests.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        tests.swap(i, j);
+    }
+}
+
 pub fn convert_benchmarks_to_tests(tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     // convert benchmarks to tests, if we're not benchmarking them
     tests.into_iter().map(|x| {
@@ -1407,6 +1937,16 @@ pub fn run_test(
         return;
     }
 
+    let runs_in_process = match testfn {
+        StaticTestFn(..) | DynTestFn(..) => opts.isolation == Isolation::Process,
+        StaticBenchFn(..) | DynBenchFn(..) => false,
+    };
+    if runs_in_process {
+        let timeout = test_timeout(opts, &desc);
+        isolation::run_test_in_process(desc, monitor_ch, opts.nocapture, timeout);
+        return;
+    }
+
     fn run_test_inner(desc: TestDesc,
                       monitor_ch: Sender<MonitorMsg>,
                       nocapture: bool,
@@ -1433,11 +1973,10 @@ pub fn run_test(
                 io::set_panic(panicio);
             };
 
-            let test_result = calc_result(&desc, result);
+            let test_result = calc_result(&desc, result.as_ref().map(|_| ()).map_err(|e| &**e));
             let stdout = data.lock().unwrap().to_vec();
-            monitor_ch
-                .send((desc.clone(), test_result, stdout))
-                .unwrap();
+            // The receiver is gone if the test timed out and the run is over.
+            let _ = monitor_ch.send((desc.clone(), test_result, stdout));
         };
 
 
@@ -1485,11 +2024,11 @@ fn __rust_begin_short_backtrace<F: FnOnce()>(f: F) {
     f()
 }
 
-fn calc_result(desc: &TestDesc, task_result: Result<(), Box<Any + Send>>) -> TestResult {
+fn calc_result(desc: &TestDesc, task_result: Result<(), &(Any + Send)>) -> TestResult {
     match (&desc.should_panic, task_result) {
         (&ShouldPanic::No, Ok(())) |
         (&ShouldPanic::Yes, Err(_)) => TrOk,
-        (&ShouldPanic::YesWithMessage(msg), Err(ref err)) => {
+        (&ShouldPanic::YesWithMessage(msg), Err(err)) => {
             if err.downcast_ref::<String>()
                 .map(|e| &**e)
                 .or_else(|| err.downcast_ref::<&'static str>().map(|e| *e))
@@ -1578,7 +2117,9 @@ impl Bencher {
             return;
         }
 
-        self.summary = Some(iter(&mut inner));
+        let (summary, samples) = iter_samples(&mut inner);
+        self.summary = Some(summary);
+        self.samples = samples;
     }
 
     pub fn bench<F>(&mut self, mut f: F) -> Option<stats::Summary>
@@ -1607,6 +2148,14 @@ where
 
 
 pub fn iter<T, F>(inner: &mut F) -> stats::Summary
+where
+    F: FnMut() -> T,
+{
+    iter_samples(inner).0
+}
+
+/// Like `iter`, but also returns the samples the summary was computed from.
+fn iter_samples<T, F>(inner: &mut F) -> (stats::Summary, Vec<f64>)
 where
     F: FnMut() -> T,
 {
@@ -1652,13 +2201,13 @@ where
         if loop_run > Duration::from_millis(100) && summ.median_abs_dev_pct < 1.0 &&
             summ.median - summ5.median < summ5.median_abs_dev
         {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         total_run = total_run + loop_run;
         // Longest we ever run for is 3s.
         if total_run > Duration::from_secs(3) {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         // If we overflow here just return the results so far. We check a
@@ -1668,7 +2217,7 @@ where
         n = match n.checked_mul(10) {
             Some(_) => n * 2,
             None => {
-                return summ5;
+                return (summ5, samples.to_vec());
             }
         };
     }
@@ -1689,6 +2238,7 @@ pub mod bench {
         let mut bs = Bencher {
             mode: BenchMode::Auto,
             summary: None,
+            samples: Vec::new(),
             bytes: 0,
         };
 
@@ -1718,7 +2268,9 @@ pub mod bench {
 
                 let bs = BenchSamples {
                     ns_iter_summ,
+                    ns_iter_samples: bs.samples.clone(),
                     mb_s: mb_s as usize,
+                    comparison: None,
                 };
                 TestResult::TrBench(bs)
             }
@@ -1728,7 +2280,9 @@ pub mod bench {
                 let samples: &mut [f64] = &mut [0.0_f64; 1];
                 let bs = BenchSamples {
                     ns_iter_summ: stats::Summary::new(samples),
+                    ns_iter_samples: Vec::new(),
                     mb_s: 0,
+                    comparison: None,
                 };
                 TestResult::TrBench(bs)
             }
@@ -1748,6 +2302,7 @@ pub mod bench {
         let mut bs = Bencher {
             mode: BenchMode::Single,
             summary: None,
+            samples: Vec::new(),
             bytes: 0,
         };
         bs.bench(f);
@@ -1762,6 +2317,8 @@ mod tests {
     use std::sync::mpsc::channel;
     use bench;
     use Bencher;
+    use {run_tests, shuffle_tests, Isolation, TrTimedOut};
+    use TestEvent::TeResult;
 
     #[test]
     pub fn do_not_run_ignored_tests() {
@@ -1774,6 +2331,7 @@ mod tests {
                 ignore: true,
                 should_panic: ShouldPanic::No,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1792,6 +2350,7 @@ mod tests {
                 ignore: true,
                 should_panic: ShouldPanic::No,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1812,6 +2371,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::Yes,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1832,6 +2392,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::YesWithMessage("error message"),
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1854,6 +2415,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::YesWithMessage(expected),
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1872,6 +2434,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::Yes,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1881,6 +2444,149 @@ mod tests {
         assert!(res == TrFailed);
     }
 
+    #[test]
+    fn test_timeout_reports_hung_test() {
+        // The test hangs until `release_tx` is dropped.
+        let (release_tx, release_rx) = channel::<()>();
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+                timeout: Some(1),
+            },
+            testfn: DynTestFn(Box::new(move || {
+                let _ = release_rx.recv();
+            })),
+        };
+        let opts = TestOpts {
+            run_tests: true,
+            ..TestOpts::new()
+        };
+        let mut results = Vec::new();
+        run_tests(&opts, vec![desc], |event| {
+            if let TeResult(_, res, _, _) = event {
+                results.push(res);
+            }
+            Ok(())
+        }).unwrap();
+        drop(release_tx);
+        assert!(results == vec![TrTimedOut(1)]);
+    }
+
+    #[test]
+    fn test_timeout_runs_remaining_tests() {
+        let (release_tx, release_rx) = channel::<()>();
+        let hung = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("hung"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+                timeout: Some(1),
+            },
+            testfn: DynTestFn(Box::new(move || {
+                let _ = release_rx.recv();
+            })),
+        };
+        let next = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("next"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+                timeout: None,
+            },
+            testfn: DynTestFn(Box::new(move || {})),
+        };
+        let opts = TestOpts {
+            run_tests: true,
+            test_threads: Some(1),
+            ..TestOpts::new()
+        };
+        let mut results = Vec::new();
+        run_tests(&opts, vec![hung, next], |event| {
+            if let TeResult(desc, res, _, _) = event {
+                results.push((desc.name.to_string(), res));
+            }
+            Ok(())
+        }).unwrap();
+        drop(release_tx);
+        assert!(results == vec![("hung".to_string(), TrTimedOut(1)),
+                                ("next".to_string(), TrOk)]);
+    }
+
+    #[test]
+    fn parse_test_timeout_flag() {
+        let args = vec![
+            "progname".to_string(),
+            "--test-timeout".to_string(),
+            "30".to_string(),
+            "-Zunstable-options".to_string(),
+        ];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_test_timeout_flag"),
+        };
+        assert_eq!(opts.test_timeout, Some(30));
+    }
+
+    #[test]
+    fn parse_isolation_flag() {
+        let args = vec![
+            "progname".to_string(),
+            "--isolation=process".to_string(),
+            "-Zunstable-options".to_string(),
+        ];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_isolation_flag"),
+        };
+        assert_eq!(opts.isolation, Isolation::Process);
+
+        let args = vec!["progname".to_string(), "--isolation=process".to_string()];
+        assert!(parse_opts(&args).unwrap().is_err());
+    }
+
+    #[test]
+    fn parse_timing_flags() {
+        let args = vec![
+            "progname".to_string(),
+            "--report-time".to_string(),
+            "--slowest=5".to_string(),
+            "-Zunstable-options".to_string(),
+        ];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_timing_flags"),
+        };
+        assert!(opts.report_time);
+        assert_eq!(opts.slowest, Some(5));
+    }
+
+    #[test]
+    fn baseline_comparison_flags_regressions() {
+        use baseline::Baseline;
+
+        let old = (0..50).map(|i| 1000.0 + (i % 5) as f64).collect::<Vec<_>>();
+        let slower = old.iter().map(|x| x * 1.1).collect::<Vec<_>>();
+        let noisy = old.iter().rev().cloned().collect::<Vec<_>>();
+
+        let mut baseline = Baseline::new();
+        baseline.insert("bench", old);
+
+        let cmp = baseline.compare("bench", &slower).unwrap();
+        assert!(cmp.is_regression());
+        assert_eq!(cmp.verdict(), "regressed");
+
+        let cmp = baseline.compare("bench", &noisy).unwrap();
+        assert!(!cmp.is_regression() && !cmp.is_improvement());
+        assert_eq!(cmp.verdict(), "no change");
+
+        assert!(baseline.compare("other", &slower).is_none());
+    }
+
     #[test]
     fn parse_ignored_flag() {
         let args = vec![
@@ -1911,6 +2617,7 @@ mod tests {
                                  ignore: true,
                                  should_panic: ShouldPanic::No,
                                  allow_fail: false,
+                                 timeout: None,
                              },
                              testfn: DynTestFn(Box::new(move || {})),
                          },
@@ -1920,6 +2627,7 @@ mod tests {
                                  ignore: false,
                                  should_panic: ShouldPanic::No,
                                  allow_fail: false,
+                                 timeout: None,
                              },
                              testfn: DynTestFn(Box::new(move || {})),
                          }];
@@ -1942,6 +2650,7 @@ mod tests {
                             ignore: false,
                             should_panic: ShouldPanic::No,
                             allow_fail: false,
+                            timeout: None,
                         },
                         testfn: DynTestFn(Box::new(move || {}))
                     }
@@ -2025,6 +2734,43 @@ mod tests {
         assert_eq!(exact.len(), 1);
     }
 
+    #[test]
+    pub fn shards_are_disjoint_and_complete() {
+        fn tests() -> Vec<TestDescAndFn> {
+            (0..10)
+                .map(|i| {
+                    TestDescAndFn {
+                        desc: TestDesc {
+                            name: DynTestName(format!("test{}", i)),
+                            ignore: false,
+                            should_panic: ShouldPanic::No,
+                            allow_fail: false,
+                            timeout: None,
+                        },
+                        testfn: DynTestFn(Box::new(move || {})),
+                    }
+                }).collect()
+        }
+
+        let mut names = Vec::new();
+        for shard_index in 0..3 {
+            let shard = filter_tests(
+                &TestOpts {
+                    shard_index,
+                    shard_count: 3,
+                    ..TestOpts::new()
+                },
+                tests(),
+            );
+            assert!(shard.len() == 3 || shard.len() == 4);
+            names.extend(shard.into_iter().map(|t| t.desc.name.to_string()));
+        }
+        names.sort();
+
+        let all = filter_tests(&TestOpts::new(), tests());
+        assert_eq!(names, all.into_iter().map(|t| t.desc.name.to_string()).collect::<Vec<_>>());
+    }
+
     #[test]
     pub fn sort_tests() {
         let mut opts = TestOpts::new();
@@ -2051,6 +2797,7 @@ mod tests {
                         ignore: false,
                         should_panic: ShouldPanic::No,
                         allow_fail: false,
+                        timeout: None,
                     },
                     testfn: DynTestFn(Box::new(testfn)),
                 };
@@ -2077,6 +2824,42 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn shuffle_tests_is_deterministic() {
+        fn tests() -> Vec<TestDescAndFn> {
+            (0..20)
+                .map(|i| {
+                    TestDescAndFn {
+                        desc: TestDesc {
+                            name: DynTestName(format!("test{:02}", i)),
+                            ignore: false,
+                            should_panic: ShouldPanic::No,
+                            allow_fail: false,
+                            timeout: None,
+                        },
+                        testfn: DynTestFn(Box::new(move || {})),
+                    }
+                }).collect()
+        }
+        fn names(tests: &[TestDescAndFn]) -> Vec<String> {
+            tests.iter().map(|t| t.desc.name.to_string()).collect()
+        }
+
+        let mut a = tests();
+        let mut b = tests();
+        let mut c = tests();
+        shuffle_tests(42, &mut a);
+        shuffle_tests(42, &mut b);
+        shuffle_tests(43, &mut c);
+
+        assert_eq!(names(&a), names(&b));
+        assert!(names(&a) != names(&c));
+
+        let mut sorted = names(&a);
+        sorted.sort();
+        assert_eq!(sorted, names(&tests()));
+    }
+
     #[test]
     pub fn test_metricmap_compare() {
         let mut m1 = MetricMap::new();
@@ -2125,6 +2908,7 @@ mod tests {
             ignore: false,
             should_panic: ShouldPanic::No,
             allow_fail: false,
+            timeout: None,
         };
 
         ::bench::benchmark(desc,
@@ -2147,6 +2931,7 @@ mod tests {
             ignore: false,
             should_panic: ShouldPanic::No,
             allow_fail: false,
+            timeout: None,
         };
 
         ::bench::benchmark(desc,
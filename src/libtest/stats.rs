@@ -327,6 +327,135 @@ pub fn winsorize(samples: &mut [f64], pct: f64) {
     }
 }
 
+/// Result of Welch's unequal variances t-test between two sample sets.
+#[derive(Clone, PartialEq, Copy, Debug)]
+pub struct TTest {
+    /// The t statistic; positive if the second set has the larger mean.
+    pub t: f64,
+    /// Degrees of freedom, from the Welch-Satterthwaite equation.
+    pub df: f64,
+    /// Two-tailed p-value: the probability of seeing a difference in means
+    /// at least this large if both sets came from the same distribution.
+    pub p_value: f64,
+}
+
+/// Welch's t-test for the hypothesis that `a` and `b` have the same mean,
+/// without assuming that they have the same variance. Returns `None` if
+/// either set has fewer than two samples.
+///
+/// See: <https://en.wikipedia.org/wiki/Welch%27s_t-test>
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TTest> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let sa = a.var() / na;
+    let sb = b.var() / nb;
+    let se2 = sa + sb;
+    let diff = b.mean() - a.mean();
+
+    if se2 == 0.0 {
+        // Both sets are constant, so any difference is significant.
+        let (t, p_value) = if diff == 0.0 {
+            (0.0, 1.0)
+        } else {
+            (diff.signum() * ::std::f64::INFINITY, 0.0)
+        };
+        return Some(TTest { t, df: na + nb - 2.0, p_value });
+    }
+
+    let t = diff / se2.sqrt();
+    let df = se2 * se2 / (sa * sa / (na - 1.0) + sb * sb / (nb - 1.0));
+    Some(TTest { t, df, p_value: student_t_two_tailed(t, df) })
+}
+
+/// Probability that the absolute value of a Student's t-distributed variable
+/// with `df` degrees of freedom exceeds `|t|`.
+fn student_t_two_tailed(t: f64, df: f64) -> f64 {
+    incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// The regularized incomplete beta function I_x(a, b), evaluated with the
+/// continued fraction from Numerical Recipes (section 6.4).
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) +
+                 a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly only for x < (a + 1) / (a + b + 2),
+    // use the symmetry I_x(a, b) = 1 - I_(1-x)(b, a) otherwise.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 200;
+    const EPSILON: f64 = 3.0e-14;
+    const TINY: f64 = 1.0e-300;
+
+    let clamp = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    // Modified Lentz's method
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..MAX_ITERATIONS + 1 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+
+        // Even step
+        let aa = m * (b - m) * x / ((a - 1.0 + m2) * (a + m2));
+        d = 1.0 / clamp(1.0 + aa * d);
+        c = clamp(1.0 + aa / c);
+        h *= d * c;
+
+        // Odd step
+        let aa = -(a + m) * (a + b + m) * x / ((a + m2) * (a + 1.0 + m2));
+        d = 1.0 / clamp(1.0 + aa * d);
+        c = clamp(1.0 + aa / c);
+        let delta = d * c;
+        h *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+/// Natural logarithm of the gamma function for `x > 0`, using the Lanczos
+/// approximation with g = 7.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.99999999999980993,
+        676.5203681218851,
+        -1259.1392167224028,
+        771.32342877765313,
+        -176.61502916214059,
+        12.507343278686905,
+        -0.13857109526572012,
+        9.9843695780195716e-6,
+        1.5056327351493116e-7,
+    ];
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let mut sum = COEFFICIENTS[0];
+    for (i, c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    0.5 * (2.0 * ::std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
 // Test vectors generated from R, using the script src/etc/stat-test-vectors.r.
 
 #[cfg(test)]
@@ -894,6 +1023,25 @@ mod tests {
         check(val, summ);
     }
 
+    #[test]
+    fn test_student_t_two_tailed() {
+        assert_approx_eq!(super::student_t_two_tailed(1.0, 1.0), 0.5);
+        assert_approx_eq!(super::student_t_two_tailed(2.228138851986, 10.0), 0.05);
+        assert_approx_eq!(super::student_t_two_tailed(0.0, 5.0), 1.0);
+    }
+
+    #[test]
+    fn test_welch_t_test() {
+        // R: t.test(c(3, 4, 5, 6, 7, 8), c(1, 2, 3, 4, 5))
+        let res = super::welch_t_test(&[1.0, 2.0, 3.0, 4.0, 5.0],
+                                      &[3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+        assert_approx_eq!(res.t, 2.4019223071);
+        assert_approx_eq!(res.df, 8.9893617021);
+        assert_approx_eq!(res.p_value, 0.0398030820);
+
+        assert!(super::welch_t_test(&[1.0], &[1.0, 2.0]).is_none());
+    }
+
     #[test]
     fn test_sum_f64s() {
         assert_eq!([0.5f64, 3.2321f64, 1.5678f64].sum(), 5.2999);
@@ -11,6 +11,7 @@
 // Check that CopyPropagation does not propagate an assignment to a function argument
 // (doing so can break usages of the original argument value)
 
+#[inline(never)]
 fn dummy(x: u8) -> u8 {
     x
 }
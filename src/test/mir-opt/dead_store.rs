@@ -0,0 +1,65 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that DeadStoreElimination removes assignments that are overwritten
+// before they are read, but keeps the ones to borrowed locals
+
+fn overwrite(mut x: u32) -> u32 {
+    x = 1;
+    x = 2;
+    x
+}
+
+fn borrowed() -> u32 {
+    let mut x = 1;
+    let p = &x as *const u32;
+    x = 2; // read through `p`, which liveness does not see
+    unsafe { *p }
+}
+
+fn main() {
+    // Make sure the functions actually get instantiated.
+    overwrite(0);
+    borrowed();
+}
+
+// END RUST SOURCE
+// START rustc.overwrite.DeadStoreElimination.before.mir
+// bb0: {
+//     ...
+//     _1 = const 1u32;
+//     _1 = const 2u32;
+//     ...
+//     return;
+// }
+// END rustc.overwrite.DeadStoreElimination.before.mir
+// START rustc.overwrite.DeadStoreElimination.after.mir
+// bb0: {
+//     ...
+//     nop;
+//     _1 = const 2u32;
+//     ...
+//     return;
+// }
+// END rustc.overwrite.DeadStoreElimination.after.mir
+// START rustc.borrowed.DeadStoreElimination.before.mir
+// bb0: {
+//     ...
+//     _1 = const 2u32;
+//     ...
+// }
+// END rustc.borrowed.DeadStoreElimination.before.mir
+// START rustc.borrowed.DeadStoreElimination.after.mir
+// bb0: {
+//     ...
+//     _1 = const 2u32;
+//     ...
+// }
+// END rustc.borrowed.DeadStoreElimination.after.mir
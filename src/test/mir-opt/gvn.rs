@@ -0,0 +1,51 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that GlobalValueNumbering reuses the length and the bounds check of
+// the first indexing expression for the second one
+
+fn index_twice(a: &[u32], i: usize) -> u32 {
+    a[i] + a[i]
+}
+
+fn main() {
+    // Make sure the function actually gets instantiated.
+    index_twice(&[1, 2], 0);
+}
+
+// END RUST SOURCE
+// START rustc.index_twice.GlobalValueNumbering.before.mir
+// bb0: {
+//     ...
+//     _5 = Len((*_1));
+//     _6 = Lt(_4, _5);
+//     ...
+// }
+// bb1: {
+//     ...
+//     _9 = Len((*_1));
+//     _10 = Lt(_8, _9);
+//     ...
+// }
+// END rustc.index_twice.GlobalValueNumbering.before.mir
+// START rustc.index_twice.GlobalValueNumbering.after.mir
+// bb0: {
+//     ...
+//     _5 = Len((*_1));
+//     _6 = Lt(_4, _5);
+//     ...
+// }
+// bb1: {
+//     ...
+//     _9 = _5;
+//     _10 = _6;
+//     ...
+// }
+// END rustc.index_twice.GlobalValueNumbering.after.mir
@@ -0,0 +1,46 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Tests that local non-generic functions are inlined into callers whose MIR
+// is not exported, but not into callers whose MIR is exported, since that
+// MIR can't refer to functions whose MIR isn't exported.
+
+fn main() {
+    println!("{}", local_caller());
+    println!("{}", exported_caller::<u8>());
+}
+
+fn local_caller() -> u32 {
+    two()
+}
+
+fn exported_caller<T>() -> u32 {
+    two()
+}
+
+fn two() -> u32 {
+    2
+}
+
+// END RUST SOURCE
+// START rustc.local_caller.Inline.after.mir
+// bb0: {
+//     ...
+//     _0 = const 2u32;
+//     ...
+//     return;
+// }
+// END rustc.local_caller.Inline.after.mir
+// START rustc.exported_caller.Inline.after.mir
+// bb0: {
+//     ...
+//     _0 = const two() -> bb1;
+// }
+// END rustc.exported_caller.Inline.after.mir
@@ -0,0 +1,90 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+fn known(x: u32) -> u32 {
+    match Some(x) {
+        Some(y) => y,
+        None => 0,
+    }
+}
+
+fn threaded(c: bool) -> u32 {
+    let o = if c { Some(1) } else { None };
+    match o {
+        Some(y) => y,
+        None => 0,
+    }
+}
+
+fn main() {
+    known(1);
+    threaded(true);
+}
+
+// END RUST SOURCE
+// START rustc.known.SimplifyBranches-after-const-prop.before.mir
+// bb0: {
+//     ...
+//     _2 = std::option::Option<u32>::Some(move _3,);
+//     StorageDead(_3);
+//     _4 = discriminant(_2);
+//     switchInt(move _4) -> [0isize: bb2, 1isize: bb3, otherwise: bb1];
+// }
+// END rustc.known.SimplifyBranches-after-const-prop.before.mir
+// START rustc.known.SimplifyBranches-after-const-prop.after.mir
+// bb0: {
+//     ...
+//     _2 = std::option::Option<u32>::Some(move _3,);
+//     StorageDead(_3);
+//     _4 = discriminant(_2);
+//     goto -> bb3;
+// }
+// END rustc.known.SimplifyBranches-after-const-prop.after.mir
+// START rustc.threaded.SimplifyBranches-after-const-prop.before.mir
+// bb1: {
+//     _2 = std::option::Option<u32>::Some(const 1u32,);
+//     goto -> bb3;
+// }
+// bb2: {
+//     _2 = std::option::Option<u32>::None;
+//     goto -> bb3;
+// }
+// bb3: {
+//     StorageDead(_3);
+//     _5 = discriminant(_2);
+//     switchInt(move _5) -> [0isize: bb5, 1isize: bb6, otherwise: bb4];
+// }
+// END rustc.threaded.SimplifyBranches-after-const-prop.before.mir
+// START rustc.threaded.SimplifyBranches-after-const-prop.after.mir
+// bb1: {
+//     _2 = std::option::Option<u32>::Some(const 1u32,);
+//     goto -> bb8;
+// }
+// bb2: {
+//     _2 = std::option::Option<u32>::None;
+//     goto -> bb9;
+// }
+// bb3: {
+//     StorageDead(_3);
+//     _5 = discriminant(_2);
+//     switchInt(move _5) -> [0isize: bb5, 1isize: bb6, otherwise: bb4];
+// }
+// ...
+// bb8: {
+//     StorageDead(_3);
+//     _5 = discriminant(_2);
+//     goto -> bb6;
+// }
+// bb9: {
+//     StorageDead(_3);
+//     _5 = discriminant(_2);
+//     goto -> bb5;
+// }
+// END rustc.threaded.SimplifyBranches-after-const-prop.after.mir
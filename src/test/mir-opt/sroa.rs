@@ -0,0 +1,63 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that ScalarReplacementOfAggregates splits a tuple that is only
+// accessed field by field, but not one that is borrowed as a whole
+
+fn pair(x: u32, y: u32) -> u32 {
+    let p = (x, y);
+    p.0
+}
+
+fn borrowed(x: u32, y: u32) -> u32 {
+    let p = (x, y);
+    let r = &p;
+    r.0
+}
+
+fn main() {
+    // Make sure the functions actually get instantiated.
+    pair(0, 1);
+    borrowed(0, 1);
+}
+
+// END RUST SOURCE
+// START rustc.pair.ScalarReplacementOfAggregates.before.mir
+// bb0: {
+//     ...
+//     (_3.0: u32) = move _4;
+//     (_3.1: u32) = move _5;
+//     ...
+//     _6 = (_3.0: u32);
+//     _0 = move _6;
+//     ...
+//     return;
+// }
+// END rustc.pair.ScalarReplacementOfAggregates.before.mir
+// START rustc.pair.ScalarReplacementOfAggregates.after.mir
+// bb0: {
+//     ...
+//     _7 = move _4;
+//     _8 = move _5;
+//     ...
+//     _6 = _7;
+//     _0 = move _6;
+//     ...
+//     return;
+// }
+// END rustc.pair.ScalarReplacementOfAggregates.after.mir
+// START rustc.borrowed.ScalarReplacementOfAggregates.after.mir
+// bb0: {
+//     ...
+//     (_3.0: u32) = move _4;
+//     (_3.1: u32) = move _5;
+//     ...
+// }
+// END rustc.borrowed.ScalarReplacementOfAggregates.after.mir
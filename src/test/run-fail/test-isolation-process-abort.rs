@@ -0,0 +1,27 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that a test aborting the process is reported as a failure of that
+// test only when running with `--isolation=process`.
+
+// check-stdout
+// error-pattern:test process terminated abnormally
+// compile-flags: --test
+// run-flags: --isolation=process -Zunstable-options
+// ignore-emscripten no processes
+// ignore-wasm32-bare no processes
+
+#[test]
+fn aborts() {
+    std::process::abort();
+}
+
+#[test]
+fn passes() {}
@@ -0,0 +1,23 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+fn main() {
+    let _a = (1 / (2 + 3));
+    let _b = 1;
+    let _c = 2;
+    let _d = 3;
+    let _e = 4;
+    let _f = 5;
+    let _g = 6;
+    let _h = 7;
+    if (_b == 1) {
+        println!("{}", _a);
+    }
+}
@@ -0,0 +1,27 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod a {
+    pub fn sum(xs: &[u32]) -> u32 {
+        xs.iter().sum()
+    }
+}
+
+mod b {
+    pub fn product(xs: &[u32]) -> u32 {
+        xs.iter().product()
+    }
+}
+
+fn main() {
+    let xs = [1, 2, 3, 4];
+    assert_eq!(::a::sum(&xs), 10);
+    assert_eq!(::b::product(&xs), 24);
+}
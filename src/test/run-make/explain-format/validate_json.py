@@ -0,0 +1,31 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import sys
+import json
+
+with open(sys.argv[1]) as f:
+    all_codes = json.load(f)
+with open(sys.argv[2]) as f:
+    one_code = json.load(f)
+
+# All codes are listed in order, each with the markdown of its explanation
+codes = [e['code'] for e in all_codes]
+assert codes == sorted(codes)
+assert 'E0004' in codes and 'E0308' in codes
+for e in all_codes:
+    assert e['explanation'] and not e['explanation'].startswith('\n')
+
+# With --explain, only the given code is printed
+assert len(one_code) == 1
+assert one_code[0] == [e for e in all_codes if e['code'] == 'E0004'][0]
+assert '```compile_fail,E0004' in one_code[0]['explanation']
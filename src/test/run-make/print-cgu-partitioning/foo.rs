@@ -0,0 +1,30 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+pub mod a {
+    pub fn first() -> u32 {
+        ::helper() + ::double(1u32) + vec![1u32].len() as u32
+    }
+}
+
+pub mod b {
+    pub fn second() -> u64 {
+        ::helper() as u64 + ::double(2u64)
+    }
+}
+
+#[inline]
+pub fn helper() -> u32 {
+    7
+}
+
+pub fn double<T: Copy + ::std::ops::Add<Output = T>>(x: T) -> T {
+    x + x
+}
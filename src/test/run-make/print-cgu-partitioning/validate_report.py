@@ -0,0 +1,44 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import sys
+import json
+
+with open(sys.argv[1]) as f:
+    report = json.load(f)
+
+assert report['crate'] == 'foo'
+cgus = report['codegen_units']
+assert len(cgus) == 2, [cgu['name'] for cgu in cgus]
+assert [cgu['name'] for cgu in cgus] == sorted(cgu['name'] for cgu in cgus)
+
+# The three per-module units were merged into two.
+assert sorted(len(cgu['merged_from']) for cgu in cgus) == [1, 2]
+
+for cgu in cgus:
+    items = cgu['roots'] + cgu['inlined']
+    assert sum(item['size_estimate'] for item in items) == cgu['size_estimate']
+    assert sum(item['size_estimate'] for item in cgu['inlined']) == \
+        cgu['inlined_size_estimate']
+    for item in cgu['inlined']:
+        assert item['reason'] in ('local-copy', 'external')
+        assert 1 <= item['copies'] <= len(cgus)
+
+roots = [item for cgu in cgus for item in cgu['roots']]
+root_names = [item['item'] for item in roots]
+assert 'fn foo::a[0]::first[0]' in root_names, root_names
+assert 'fn foo::double[0]<u32>' in root_names, root_names
+assert all(item['placed_by'] is not None for item in roots)
+
+inlined = dict((item['item'], item) for cgu in cgus for item in cgu['inlined'])
+assert inlined['fn foo::helper[0]']['reason'] == 'local-copy'
+assert any(item['reason'] == 'external' for item in inlined.values()), list(inlined)
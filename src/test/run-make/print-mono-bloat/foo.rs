@@ -0,0 +1,21 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+fn sum<T: Copy + ::std::ops::Add<Output = T>>(values: &[T], zero: T) -> T {
+    values.iter().fold(zero, |acc, &x| acc + x)
+}
+
+fn small() -> u8 {
+    sum(&[1u8, 2], 0)
+}
+
+fn main() {
+    println!("{} {} {}", small(), sum(&[1u32, 2], 0), sum(&[1u64, 2], 0));
+}
@@ -0,0 +1,33 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import sys
+import json
+
+with open(sys.argv[1]) as f:
+    trace = json.load(f)
+
+events = [e for e in trace['traceEvents'] if e['ph'] == 'X']
+assert len(events) > 0
+for e in events:
+    assert e['cat'] in ('pass', 'task', 'query')
+    assert e['ts'] >= 0 and e['dur'] >= 0
+queries = [e for e in events if e['cat'] == 'query']
+assert any(e['name'] == 'type_of' for e in queries)
+assert set(e['args']['cache'] for e in queries) <= set(['hit', 'miss'])
+
+with open(sys.argv[2]) as f:
+    lines = f.read().splitlines()
+assert len(lines) > 0
+for line in lines:
+    stack, micros = line.rsplit(' ', 1)
+    assert stack and int(micros) > 0
@@ -0,0 +1,24 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import sys
+import json
+
+# Warnings about command line flags end up in the same log as the
+# diagnostics of the compilation
+log = json.load(sys.stdin)
+assert len(log['runs']) == 1
+
+results = log['runs'][0]['results']
+messages = [r['message']['text'] for r in results if r['level'] == 'warning']
+assert 'resetting to default -C codegen-units=1' in messages
+assert [r for r in results if r.get('ruleId') == 'E0308']
@@ -0,0 +1,49 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import sys
+import json
+
+# The whole output must be a single SARIF log
+log = json.load(sys.stdin)
+assert log['version'] == '2.1.0'
+assert len(log['runs']) == 1
+
+run = log['runs'][0]
+rules = run['tool']['driver']['rules']
+results = run['results']
+assert run['invocations'][0]['executionSuccessful'] is False
+
+for result in results:
+    if 'ruleIndex' in result:
+        assert rules[result['ruleIndex']]['id'] == result['ruleId']
+
+# The type error, with the explanation of its error code
+mismatch = [r for r in results if r.get('ruleId') == 'E0308']
+assert len(mismatch) == 1
+assert mismatch[0]['level'] == 'error'
+location = mismatch[0]['locations'][0]['physicalLocation']
+assert location['artifactLocation']['uri'] == 'foo.rs'
+assert location['region']['startLine'] == 12
+assert location['region']['startColumn'] == 18
+assert location['region']['endColumn'] == 25
+assert 'fullDescription' in rules[mismatch[0]['ruleIndex']]
+
+# The lint, with the suggestion to remove the parentheses as a fix
+unused_parens = [r for r in results if r.get('ruleId') == 'unused_parens']
+assert len(unused_parens) == 1
+assert unused_parens[0]['level'] == 'warning'
+fix = unused_parens[0]['fixes'][0]
+replacement = fix['artifactChanges'][0]['replacements'][0]
+assert replacement['insertedContent']['text'] == '1 / (2 + 3)'
+assert replacement['deletedRegion']['startLine'] == 13
+assert replacement['deletedRegion']['startColumn'] == 14
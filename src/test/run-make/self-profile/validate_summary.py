@@ -0,0 +1,40 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import sys
+import json
+
+with open(sys.argv[1]) as f:
+    summary = json.load(f)
+
+items = summary['items']
+assert summary['total'] >= summary['unattributed'] >= 0
+assert len(items) > 0
+
+# Items are sorted by time, and their time is the sum of their queries.
+times = [item['time'] for item in items]
+assert times == sorted(times, reverse=True)
+for item in items:
+    assert item['kind'] in ('def', 'trans_item')
+    assert abs(sum(item['queries'].values()) - item['time']) < 1e-6
+
+defs = [item for item in items if item['kind'] == 'def' and item['crate'] == 'foo']
+assert any(item['item'] == 'main' and 'typeck_tables_of' in item['queries']
+           for item in defs)
+
+# Every instantiation of `sum` is translated separately.
+sums = [item['item'] for item in items
+        if item['kind'] == 'trans_item' and item['item'].startswith('foo::sum')]
+assert any('u8' in name for name in sums), sums
+assert any('u32' in name for name in sums), sums
+
+assert 'foo' in [c['crate'] for c in summary['crates']]
@@ -0,0 +1,86 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -O
+
+// Tests that calls of provided methods through trait objects are not
+// replaced by the default body of the method when optimizing.
+
+use std::error::Error;
+use std::fmt;
+
+trait Shape {
+    fn sides(&self) -> u32 {
+        0
+    }
+}
+
+struct Circle;
+struct Square;
+
+impl Shape for Circle {}
+
+impl Shape for Square {
+    fn sides(&self) -> u32 {
+        4
+    }
+}
+
+fn sides(shape: &Shape) -> u32 {
+    shape.sides()
+}
+
+fn sides_of<T: Shape>(shape: &T) -> u32 {
+    sides(shape)
+}
+
+#[derive(Debug)]
+struct Inner;
+#[derive(Debug)]
+struct Outer(Inner);
+
+impl fmt::Display for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("inner")
+    }
+}
+
+impl fmt::Display for Outer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("outer")
+    }
+}
+
+impl Error for Inner {
+    fn description(&self) -> &str {
+        "inner"
+    }
+}
+
+impl Error for Outer {
+    fn description(&self) -> &str {
+        "outer"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        Some(&self.0)
+    }
+}
+
+fn main() {
+    assert_eq!(sides(&Circle), 0);
+    assert_eq!(sides(&Square), 4);
+    assert_eq!(sides_of(&Square), 4);
+
+    let err: &Error = &Outer(Inner);
+    assert_eq!(err.cause().map(|cause| cause.to_string()), Some("inner".to_string()));
+    let inner: &Error = &Inner;
+    assert!(inner.cause().is_none());
+}
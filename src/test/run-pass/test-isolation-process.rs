@@ -0,0 +1,35 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: --test
+// run-flags: --isolation=process -Zunstable-options
+// ignore-emscripten no processes
+// ignore-wasm32-bare no processes
+
+#[test]
+fn passes() {
+    println!("hello from a child process");
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn panics() {
+    panic!("boom");
+}
+
+#[test]
+fn catches_panic() {
+    assert!(std::panic::catch_unwind(|| panic!("caught")).is_err());
+}
+
+#[test]
+fn joins_panicking_thread() {
+    assert!(std::thread::spawn(|| panic!("helper")).join().is_err());
+}
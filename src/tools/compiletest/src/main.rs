@@ -498,6 +498,16 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         color: config.color,
         test_threads: None,
         skip: vec![],
+        test_timeout: None,
+        isolation: test::Isolation::Thread,
+        shuffle: false,
+        shuffle_seed: None,
+        report_time: false,
+        slowest: None,
+        shard_index: 0,
+        shard_count: 1,
+        save_baseline: None,
+        baseline: None,
         list: false,
         options: test::Options::new(),
     }
@@ -625,6 +635,7 @@ pub fn make_test(config: &Config, testpaths: &TestPaths) -> test::TestDescAndFn
             ignore,
             should_panic,
             allow_fail: false,
+            timeout: None,
         },
         testfn: make_test_closure(config, testpaths),
     }